@@ -0,0 +1,67 @@
+use criterion::{criterion_group, criterion_main, Throughput, Criterion, BenchmarkId, black_box};
+use lzrs_lib::{Writer, Config, HashChain, MatchFinder};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::io::Write;
+
+const KB: usize = 1024;
+const SIZE: usize = 64*KB;
+const INSERT_SIZE: usize = 1024*KB;
+
+/// Compresses a run of a single byte, where every position is a candidate match. Without a chain
+/// limit, every token visits the whole dictionary.
+pub fn chain_benchmark_run(c: &mut Criterion) {
+    let input = vec![b'a'; SIZE];
+
+    let mut group = c.benchmark_group("chain_run");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(SIZE as u64));
+
+    for (name, max_chain_length) in [("32", 32), ("unlimited", usize::MAX)] {
+        group.bench_with_input(BenchmarkId::from_parameter(name), &max_chain_length, |b, max_chain_length: &usize| {
+            b.iter(|| {
+                let config = Config::builder()
+                    .dict_size(SIZE)
+                    .max_chain_length(*max_chain_length)
+                    .build();
+                let mut comp = Writer::new(Vec::new(), config);
+                comp.write_all(black_box(&input)).unwrap();
+                comp.finish().unwrap()
+            });
+        });
+    }
+    group.finish();
+}
+
+/// Inserts every position of random bytes into the hash chains, either one at a time or all at
+/// once, for the throughput of the insertion alone.
+pub fn insert_benchmark(c: &mut Criterion) {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut input = vec![0; INSERT_SIZE];
+    rng.fill(&mut input[..]);
+    let config = Config::builder().dict_size(32*KB).build();
+
+    let mut group = c.benchmark_group("chain_insert");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(INSERT_SIZE as u64));
+
+    group.bench_function("each", |b| {
+        b.iter(|| {
+            let mut finder = HashChain::with_config(&config);
+            for i in 0..INSERT_SIZE {
+                finder.insert(i, black_box(&input[i..]));
+            }
+            finder
+        });
+    });
+    group.bench_function("many", |b| {
+        b.iter(|| {
+            let mut finder = HashChain::with_config(&config);
+            finder.insert_many(0, black_box(&input), INSERT_SIZE);
+            finder
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(chain, chain_benchmark_run, insert_benchmark);
+criterion_main!(chain);
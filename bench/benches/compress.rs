@@ -0,0 +1,93 @@
+use criterion::{criterion_group, criterion_main, Throughput, Criterion, BenchmarkId, black_box};
+use lzrs_lib::{Writer, Config};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::io::Write;
+
+const KB: usize = 1024;
+const SIZE: usize = 64*KB;
+const DICT_SIZES: [usize; 3] = [4*KB, 32*KB, 64*KB];
+
+const WORDS: [&str; 24] = [
+    "the", "of", "and", "to", "in", "a", "is", "that", "for", "it", "as", "was", "with", "be",
+    "by", "on", "not", "he", "this", "are", "or", "his", "from", "compression",
+];
+
+/// Words drawn at random from a small vocabulary, with some punctuation.
+fn text(rng: &mut StdRng) -> Vec<u8> {
+    let mut out = String::new();
+    while out.len() < SIZE {
+        out.push_str(WORDS[rng.gen_range(0..WORDS.len())]);
+        out.push_str(if rng.gen_ratio(1, 12) { ". " } else { " " });
+    }
+    out.truncate(SIZE);
+    out.into_bytes()
+}
+
+/// An array of records that all share the same keys.
+fn json(rng: &mut StdRng) -> Vec<u8> {
+    let mut out = String::from("[");
+    while out.len() < SIZE {
+        out.push_str(&format!(
+            r#"{{"id":{},"name":"{}","score":{:.3},"active":{}}},"#,
+            rng.gen::<u32>(),
+            WORDS[rng.gen_range(0..WORDS.len())],
+            rng.gen::<f64>() * 100.0,
+            rng.gen::<bool>(),
+        ));
+    }
+    out.truncate(SIZE);
+    out.into_bytes()
+}
+
+fn random(rng: &mut StdRng) -> Vec<u8> {
+    let mut out = vec![0; SIZE];
+    rng.fill(&mut out[..]);
+    out
+}
+
+/// Runs of a random byte with random lengths.
+fn runs(rng: &mut StdRng) -> Vec<u8> {
+    let mut out = Vec::with_capacity(SIZE);
+    while out.len() < SIZE {
+        let byte = rng.gen::<u8>();
+        let len = rng.gen_range(1..KB);
+        out.extend(std::iter::repeat_n(byte, len));
+    }
+    out.truncate(SIZE);
+    out
+}
+
+/// Compresses each kind of input with each dictionary size, for the throughput of the whole
+/// `Writer`.
+pub fn compress_benchmark(c: &mut Criterion) {
+    let mut rng = StdRng::seed_from_u64(0);
+    let inputs = [
+        ("text", text(&mut rng)),
+        ("json", json(&mut rng)),
+        ("random", random(&mut rng)),
+        ("runs", runs(&mut rng)),
+    ];
+
+    for (name, input) in inputs.iter() {
+        let mut group = c.benchmark_group(format!("compress_{}", name));
+        group.sample_size(10);
+        group.throughput(Throughput::Bytes(input.len() as u64));
+
+        for dict_size in DICT_SIZES {
+            group.bench_with_input(BenchmarkId::from_parameter(dict_size), &dict_size, |b, dict_size: &usize| {
+                b.iter(|| {
+                    let config = Config::builder()
+                        .dict_size(*dict_size)
+                        .build();
+                    let mut comp = Writer::new(Vec::new(), config);
+                    comp.write_all(black_box(input)).unwrap();
+                    comp.finish().unwrap()
+                });
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(compress, compress_benchmark);
+criterion_main!(compress);
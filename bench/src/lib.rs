@@ -10,11 +10,7 @@ pub mod batched;
 use rand::{Fill, self};
 
 pub fn rand_buffer(size: usize) -> Box<[u8]> {
-    let mut buf: Box<[u8]> = {
-        let mut v = Vec::with_capacity(size);
-        unsafe { v.set_len(size); }
-        v.into_boxed_slice()
-    };
+    let mut buf: Box<[u8]> = vec![0; size].into_boxed_slice();
     buf.try_fill(&mut rand::thread_rng()).unwrap();
     buf
 }
@@ -74,12 +70,7 @@ impl DictTest {
     }
 
     pub fn setup(&self, buffer: &DictBuf) -> (usize, usize, Box<[u8]>) {
-        let mut query = {
-            let size = self.query_size.unwrap();
-            let mut vec: Vec<u8> = Vec::with_capacity(size);
-            unsafe { vec.set_len(size); }
-            vec.into_boxed_slice()
-        };
+        let mut query = vec![0; self.query_size.unwrap()].into_boxed_slice();
 
         // Set up the query string
         let (dist, len) = match self.match_type.unwrap() {
@@ -136,14 +127,10 @@ pub struct DictBuf {
 }
 
 impl DictBuf {
-    /// Create an uninitialized buffer of `size` bytes.
+    /// Create a zeroed buffer of `size` bytes.
     pub fn new(size: usize) -> Self {
-        let mut vec = Vec::with_capacity(size);
-        unsafe { 
-            vec.set_len(size);
-        }
         Self {
-            buf: vec.into_boxed_slice(),
+            buf: vec![0; size].into_boxed_slice(),
             head: 0,
             la_len: None,
         }
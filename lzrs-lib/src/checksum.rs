@@ -0,0 +1,240 @@
+//! Checksums of the uncompressed data, used to verify a stream decoded correctly.
+
+/// The checksum stored in the trailer of a stream.
+#[repr(u8)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Checksum {
+    /// No checksum, so the stream has no trailer.
+    None = 0,
+    /// Adler-32, as used by zlib. This is cheaper to compute than CRC-32.
+    Adler32 = 1,
+    /// CRC-32 with the IEEE polynomial, as used by gzip.
+    Crc32 = 2,
+}
+
+impl Checksum {
+    /// Returns the checksum stored as `byte` in a header, if there is one.
+    pub(crate) fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(Checksum::None),
+            1 => Some(Checksum::Adler32),
+            2 => Some(Checksum::Crc32),
+            _ => None,
+        }
+    }
+}
+
+/// A running checksum of whichever kind was chosen.
+#[derive(Copy, Clone, Debug)]
+pub(crate) enum Hasher {
+    None,
+    Adler32(Adler32),
+    Crc32(Crc32),
+}
+
+impl Hasher {
+    pub(crate) fn new(checksum: Checksum) -> Self {
+        match checksum {
+            Checksum::None => Hasher::None,
+            Checksum::Adler32 => Hasher::Adler32(Adler32::new()),
+            Checksum::Crc32 => Hasher::Crc32(Crc32::new()),
+        }
+    }
+
+    pub(crate) fn kind(&self) -> Checksum {
+        match self {
+            Hasher::None => Checksum::None,
+            Hasher::Adler32(_) => Checksum::Adler32,
+            Hasher::Crc32(_) => Checksum::Crc32,
+        }
+    }
+
+    /// Starts over with the same kind of checksum.
+    pub(crate) fn reset(&mut self) {
+        *self = match self {
+            Hasher::None => Hasher::None,
+            Hasher::Adler32(_) => Hasher::Adler32(Adler32::new()),
+            Hasher::Crc32(_) => Hasher::Crc32(Crc32::new()),
+        }
+    }
+
+    pub(crate) fn update(&mut self, bytes: &[u8]) {
+        match self {
+            Hasher::None => {}
+            Hasher::Adler32(adler) => adler.update(bytes),
+            Hasher::Crc32(crc) => crc.update(bytes),
+        }
+    }
+
+    /// Returns the checksum of everything so far, or `None` if there is no checksum.
+    pub(crate) fn finish(&self) -> Option<u32> {
+        match self {
+            Hasher::None => None,
+            Hasher::Adler32(adler) => Some(adler.finish()),
+            Hasher::Crc32(crc) => Some(crc.finish()),
+        }
+    }
+}
+
+/// The largest prime below 2^16.
+const MOD_ADLER: u32 = 65521;
+
+/// The most bytes that can be summed before `b` could overflow a `u32`.
+const NMAX: usize = 5552;
+
+/// A running Adler-32 checksum, as used by zlib.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Adler32 {
+    a: u32,
+    b: u32,
+}
+
+impl Adler32 {
+    pub(crate) fn new() -> Self {
+        Self { a: 1, b: 0 }
+    }
+
+    pub(crate) fn update(&mut self, bytes: &[u8]) {
+        // Only reduce once per chunk instead of for every byte.
+        for chunk in bytes.chunks(NMAX) {
+            for &byte in chunk {
+                self.a += byte as u32;
+                self.b += self.a;
+            }
+            self.a %= MOD_ADLER;
+            self.b %= MOD_ADLER;
+        }
+    }
+
+    /// Returns the checksum of everything so far.
+    pub(crate) fn finish(&self) -> u32 {
+        (self.b << 16) | self.a
+    }
+}
+
+impl Default for Adler32 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The reversed IEEE polynomial.
+const CRC32_POLY: u32 = 0xedb8_8320;
+
+/// The CRC of every byte, so that bytes can be processed at once instead of bit by bit.
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ CRC32_POLY
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+/// A running CRC-32 checksum, as used by gzip.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Crc32 {
+    crc: u32,
+}
+
+impl Crc32 {
+    pub(crate) fn new() -> Self {
+        Self { crc: !0 }
+    }
+
+    pub(crate) fn update(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.crc = CRC32_TABLE[((self.crc ^ byte as u32) & 0xff) as usize] ^ (self.crc >> 8);
+        }
+    }
+
+    /// Returns the checksum of everything so far.
+    pub(crate) fn finish(&self) -> u32 {
+        !self.crc
+    }
+}
+
+impl Default for Crc32 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn adler32(bytes: &[u8]) -> u32 {
+        let mut adler = Adler32::new();
+        adler.update(bytes);
+        adler.finish()
+    }
+
+    #[test]
+    fn test_adler32() {
+        assert_eq!(1, adler32(b""));
+        assert_eq!(0x0062_0062, adler32(b"a"));
+        assert_eq!(0x11e6_0398, adler32(b"Wikipedia"));
+
+        // Long enough to need reducing, and updated in pieces.
+        let input = [0xff; 0x4000];
+        let mut adler = Adler32::new();
+        for piece in input.chunks(1000) {
+            adler.update(piece);
+        }
+        assert_eq!(0xb0d9_c3b2, adler.finish());
+        assert_eq!(adler.finish(), adler32(&input));
+    }
+
+    fn crc32(bytes: &[u8]) -> u32 {
+        let mut crc = Crc32::new();
+        crc.update(bytes);
+        crc.finish()
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(0, crc32(b""));
+        assert_eq!(0xe8b7_be43, crc32(b"a"));
+        assert_eq!(0xcbf4_3926, crc32(b"123456789"));
+
+        let mut crc = Crc32::new();
+        crc.update(b"1234");
+        crc.update(b"56789");
+        assert_eq!(0xcbf4_3926, crc.finish());
+    }
+
+    #[test]
+    fn test_hasher() {
+        for checksum in [Checksum::None, Checksum::Adler32, Checksum::Crc32] {
+            assert_eq!(Some(checksum), Checksum::from_byte(checksum as u8));
+        }
+        assert_eq!(None, Checksum::from_byte(3));
+
+        let mut hasher = Hasher::new(Checksum::Crc32);
+        hasher.update(b"garbage");
+        hasher.reset();
+        hasher.update(b"123456789");
+        assert_eq!(Some(0xcbf4_3926), hasher.finish());
+
+        assert_eq!(None, Hasher::new(Checksum::None).finish());
+        assert_eq!(Some(0x11e6_0398), {
+            let mut hasher = Hasher::new(Checksum::Adler32);
+            hasher.update(b"Wikipedia");
+            hasher.finish()
+        });
+    }
+}
@@ -0,0 +1,574 @@
+use std::{error, fmt, str::FromStr};
+
+use crate::Checksum;
+
+const DEFAULT_DICT_SIZE: usize = 0x8000;
+const DEFAULT_MIN_MATCH: usize = 3;
+const DEFAULT_MAX_MATCH: usize = 258;
+const DEFAULT_MAX_CHAIN_LENGTH: usize = 128;
+
+/// Configures a [`Writer`](crate::Writer).
+///
+/// The fields are public, but the [`ConfigBuilder`] is the recommended way to create one, since
+/// it fills in defaults for anything left out.
+///
+/// A config can also be parsed from a list of `key=value` pairs separated by commas, such as
+/// `"dict=32768,level=6,lazy"`, which is what it displays as too:
+///
+/// - The keys are the names of the fields, and `dict` is short for `dict_size`.
+/// - A `bool` field on its own is `true`.
+/// - A `checksum` is `none`, `adler32` or `crc32`.
+/// - `level` starts from [`Config::level`] instead of the defaults, wherever it is in the list,
+///   and the other keys are applied over it.
+///
+/// ```
+/// use lzrs_lib::Config;
+///
+/// let config: Config = "dict=32768,level=6,lazy".parse().unwrap();
+/// assert_eq!(Config { dict_size: 32768, ..Config::level(6) }, config);
+/// assert_eq!(config, config.to_string().parse().unwrap());
+/// ```
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Config {
+    /// The size of the sliding window that matches are searched for in.
+    pub dict_size: usize,
+
+    /// The shortest match that will be emitted as a [`Token::Rep`](crate::Token::Rep). Shorter
+    /// matches are emitted as literals instead, since they usually cost more to encode. A typical
+    /// value is 3, which is also the shortest match that can be found.
+    pub min_match: usize,
+
+    /// The longest match that will be emitted as a [`Token::Rep`](crate::Token::Rep). Longer
+    /// matches are split across several tokens.
+    pub max_match: usize,
+
+    /// The maximum number of candidate matches visited when searching for the longest match. This
+    /// bounds the time spent on inputs with many candidates, such as long runs of a single byte.
+    pub max_chain_length: usize,
+
+    /// The largest `distance` that will be emitted in a [`Token::Rep`](crate::Token::Rep), or
+    /// `None` to allow any distance in the window. Matches further back are skipped even if the
+    /// window still holds them, so the distances fit in a narrower format than the window.
+    pub max_distance: Option<usize>,
+
+    /// Whether to use lazy matching. A match is deferred by a byte if a longer match starts at the
+    /// next byte, which improves the ratio at the cost of speed.
+    pub lazy: bool,
+
+    /// Whether to use optimal parsing. Each block is buffered, and the tokens for it are chosen as
+    /// the cheapest path through the matches at every position, which gives the best ratio, at a
+    /// much higher cost in speed. This replaces lazy matching.
+    pub optimal: bool,
+
+    /// The checksum of the uncompressed data stored at the end of the stream.
+    pub checksum: Checksum,
+
+    /// The number of output bytes the writer buffers before writing the finished blocks to the
+    /// inner writer, or `None` to only write on `flush` and `finish`. The block being encoded is
+    /// always kept until it ends, so the buffer can exceed this by up to a block.
+    pub high_water_mark: Option<usize>,
+
+    /// The number of input bytes after which the writer starts over with an empty window, or
+    /// `None` to keep matching against everything before. Each of these blocks can be decoded on
+    /// its own, so a [`Reader`](crate::Reader) can seek to the start of any of them, at the cost
+    /// of the matches that would have reached across.
+    pub block_size: Option<usize>,
+
+    /// Whether the window starts small and doubles as the input needs it to, up to `dict_size`
+    /// (or as far as `max_distance` reaches). Nothing is lost when it grows, so this compresses
+    /// the same, but short inputs don't pay for the whole window.
+    pub grow_window: bool,
+
+    /// Whether every byte is emitted as a literal. The window and match finder are kept up to date
+    /// all the same, so bugs in them can be told apart from bugs in encoding the matches.
+    pub store_only: bool,
+
+    /// Whether writing fails instead of overwriting the oldest bytes of the window, so that every
+    /// match stays valid for the whole stream, or for each block with a `block_size`. Writing past
+    /// `dict_size` bytes of input fails with an `InvalidInput` error.
+    pub strict_window: bool,
+}
+
+impl Config {
+    /// Checks that the config can be used to compress.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.dict_size == 0 {
+            return Err(ConfigError::DictZero);
+        }
+        if self.dict_size > u32::MAX as usize {
+            return Err(ConfigError::DictTooLarge(self.dict_size));
+        }
+        if self.block_size == Some(0) {
+            return Err(ConfigError::BlockZero);
+        }
+        if self.min_match > self.max_match {
+            return Err(ConfigError::MatchRange {
+                min_match: self.min_match,
+                max_match: self.max_match,
+            });
+        }
+        Ok(())
+    }
+
+    /// Creates a [`ConfigBuilder`] with every field set to its default.
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder::new()
+    }
+
+    /// Creates the config for a compression level from 0 to 9, where higher levels trade speed for
+    /// a better ratio. Levels above 9 are treated as 9.
+    ///
+    /// | Level | `dict_size` | `max_chain_length` | `lazy` | `min_match` |
+    /// |-------|-------------|--------------------|--------|-------------|
+    /// | 0     | 4 KiB       | 0                  | no     | 3           |
+    /// | 1     | 4 KiB       | 4                  | no     | 4           |
+    /// | 2     | 8 KiB       | 8                  | no     | 4           |
+    /// | 3     | 16 KiB      | 16                 | no     | 3           |
+    /// | 4     | 32 KiB      | 32                 | no     | 3           |
+    /// | 5     | 32 KiB      | 64                 | yes    | 3           |
+    /// | 6     | 32 KiB      | 128                | yes    | 3           |
+    /// | 7     | 32 KiB      | 256                | yes    | 3           |
+    /// | 8     | 32 KiB      | 1024               | yes    | 3           |
+    /// | 9     | 32 KiB      | 4096               | yes    | 3           |
+    ///
+    /// Level 0 is `store_only`, so it only stores literals. Every level uses a
+    /// `max_match` of 258 and an Adler-32 checksum.
+    pub fn level(n: u8) -> Config {
+        let store_only = n == 0;
+        let (dict_size, max_chain_length, lazy, min_match) = match n {
+            0 => (0x1000, 0, false, 3),
+            1 => (0x1000, 4, false, 4),
+            2 => (0x2000, 8, false, 4),
+            3 => (0x4000, 16, false, 3),
+            4 => (0x8000, 32, false, 3),
+            5 => (0x8000, 64, true, 3),
+            6 => (0x8000, 128, true, 3),
+            7 => (0x8000, 256, true, 3),
+            8 => (0x8000, 1024, true, 3),
+            _ => (0x8000, 4096, true, 3),
+        };
+
+        Config {
+            dict_size,
+            min_match,
+            max_match: 258,
+            max_chain_length,
+            max_distance: None,
+            lazy,
+            optimal: false,
+            checksum: Checksum::Adler32,
+            high_water_mark: None,
+            block_size: None,
+            grow_window: false,
+            store_only,
+            strict_window: false,
+        }
+    }
+}
+
+/// The same config as a [`ConfigBuilder`] builds with nothing set: a 32 KiB dictionary, matches
+/// of 3 to 258 bytes, greedy parsing and an Adler-32 checksum.
+impl Default for Config {
+    fn default() -> Self {
+        ConfigBuilder::new().build()
+    }
+}
+
+impl fmt::Display for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let checksum = match self.checksum {
+            Checksum::None => "none",
+            Checksum::Adler32 => "adler32",
+            Checksum::Crc32 => "crc32",
+        };
+        write!(
+            f,
+            "dict_size={},min_match={},max_match={},max_chain_length={},lazy={},optimal={},\
+             grow_window={},store_only={},strict_window={},checksum={}",
+            self.dict_size,
+            self.min_match,
+            self.max_match,
+            self.max_chain_length,
+            self.lazy,
+            self.optimal,
+            self.grow_window,
+            self.store_only,
+            self.strict_window,
+            checksum
+        )?;
+
+        // Fields that are `None` are left out, so they stay `None` when parsed.
+        let optional = [
+            ("max_distance", self.max_distance),
+            ("high_water_mark", self.high_water_mark),
+            ("block_size", self.block_size),
+        ];
+        for (key, value) in optional {
+            if let Some(value) = value {
+                write!(f, ",{}={}", key, value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Config {
+    type Err = ConfigParseError;
+
+    /// Parses a list of `key=value` pairs, see [`Config`]. The config is validated like
+    /// [`Config::validate`] once every key is applied.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let pairs: Vec<(&str, Option<&str>)> = s
+            .split(',')
+            .map(str::trim)
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| match pair.split_once('=') {
+                Some((key, value)) => (key.trim(), Some(value.trim())),
+                None => (pair, None),
+            })
+            .collect();
+
+        let mut config = Config::default();
+        for &(key, value) in &pairs {
+            if key == "level" {
+                config = Config::level(parse_value(key, value)?);
+            }
+        }
+
+        for (key, value) in pairs {
+            match key {
+                "level" => {}
+                "dict" | "dict_size" => config.dict_size = parse_value(key, value)?,
+                "min_match" => config.min_match = parse_value(key, value)?,
+                "max_match" => config.max_match = parse_value(key, value)?,
+                "max_chain_length" => config.max_chain_length = parse_value(key, value)?,
+                "max_distance" => config.max_distance = Some(parse_value(key, value)?),
+                "lazy" => config.lazy = parse_flag(key, value)?,
+                "optimal" => config.optimal = parse_flag(key, value)?,
+                "grow_window" => config.grow_window = parse_flag(key, value)?,
+                "store_only" => config.store_only = parse_flag(key, value)?,
+                "strict_window" => config.strict_window = parse_flag(key, value)?,
+                "checksum" => {
+                    config.checksum = match value {
+                        Some("none") => Checksum::None,
+                        Some("adler32") => Checksum::Adler32,
+                        Some("crc32") => Checksum::Crc32,
+                        _ => return Err(invalid_value(key, value)),
+                    }
+                }
+                "high_water_mark" => config.high_water_mark = Some(parse_value(key, value)?),
+                "block_size" => config.block_size = Some(parse_value(key, value)?),
+                _ => return Err(ConfigParseError::UnknownKey(key.to_string())),
+            }
+        }
+
+        config.validate().map_err(ConfigParseError::Invalid)?;
+        Ok(config)
+    }
+}
+
+fn invalid_value(key: &str, value: Option<&str>) -> ConfigParseError {
+    ConfigParseError::InvalidValue {
+        key: key.to_string(),
+        value: value.unwrap_or_default().to_string(),
+    }
+}
+
+/// Parses the value of a key that has to have one.
+fn parse_value<T: FromStr>(key: &str, value: Option<&str>) -> Result<T, ConfigParseError> {
+    value
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| invalid_value(key, value))
+}
+
+/// Parses the value of a `bool` key, which is `true` if it has none.
+fn parse_flag(key: &str, value: Option<&str>) -> Result<bool, ConfigParseError> {
+    match value {
+        None => Ok(true),
+        value => parse_value(key, value),
+    }
+}
+
+/// Builds a [`Config`], falling back to defaults for anything that isn't set.
+///
+/// ```
+/// use lzrs_lib::Config;
+///
+/// let config = Config::builder()
+///     .dict_size(0x1000)
+///     .lazy(true)
+///     .build();
+///
+/// assert_eq!(0x1000, config.dict_size);
+/// assert_eq!(3, config.min_match);
+/// assert!(config.lazy);
+/// ```
+#[derive(Default)]
+pub struct ConfigBuilder {
+    dict_size: Option<usize>,
+    min_match: Option<usize>,
+    max_match: Option<usize>,
+    max_chain_length: Option<usize>,
+    max_distance: Option<usize>,
+    lazy: Option<bool>,
+    optimal: Option<bool>,
+    checksum: Option<Checksum>,
+    high_water_mark: Option<usize>,
+    block_size: Option<usize>,
+    grow_window: Option<bool>,
+    store_only: Option<bool>,
+    strict_window: Option<bool>,
+}
+
+impl ConfigBuilder {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn dict_size(self, dict_size: usize) -> Self {
+        Self {
+            dict_size: Some(dict_size),
+            ..self
+        }
+    }
+
+    pub fn min_match(self, min_match: usize) -> Self {
+        Self {
+            min_match: Some(min_match),
+            ..self
+        }
+    }
+
+    pub fn max_match(self, max_match: usize) -> Self {
+        Self {
+            max_match: Some(max_match),
+            ..self
+        }
+    }
+
+    pub fn max_chain_length(self, max_chain_length: usize) -> Self {
+        Self {
+            max_chain_length: Some(max_chain_length),
+            ..self
+        }
+    }
+
+    pub fn max_distance(self, max_distance: usize) -> Self {
+        Self {
+            max_distance: Some(max_distance),
+            ..self
+        }
+    }
+
+    pub fn lazy(self, lazy: bool) -> Self {
+        Self {
+            lazy: Some(lazy),
+            ..self
+        }
+    }
+
+    pub fn optimal(self, optimal: bool) -> Self {
+        Self {
+            optimal: Some(optimal),
+            ..self
+        }
+    }
+
+    pub fn checksum(self, checksum: Checksum) -> Self {
+        Self {
+            checksum: Some(checksum),
+            ..self
+        }
+    }
+
+    pub fn high_water_mark(self, high_water_mark: usize) -> Self {
+        Self {
+            high_water_mark: Some(high_water_mark),
+            ..self
+        }
+    }
+
+    pub fn block_size(self, block_size: usize) -> Self {
+        Self {
+            block_size: Some(block_size),
+            ..self
+        }
+    }
+
+    pub fn grow_window(self, grow_window: bool) -> Self {
+        Self {
+            grow_window: Some(grow_window),
+            ..self
+        }
+    }
+
+    pub fn store_only(self, store_only: bool) -> Self {
+        Self {
+            store_only: Some(store_only),
+            ..self
+        }
+    }
+
+    pub fn strict_window(self, strict_window: bool) -> Self {
+        Self {
+            strict_window: Some(strict_window),
+            ..self
+        }
+    }
+
+    pub fn build(self) -> Config {
+        Config {
+            dict_size: self.dict_size.unwrap_or(DEFAULT_DICT_SIZE),
+            min_match: self.min_match.unwrap_or(DEFAULT_MIN_MATCH),
+            max_match: self.max_match.unwrap_or(DEFAULT_MAX_MATCH),
+            max_chain_length: self.max_chain_length.unwrap_or(DEFAULT_MAX_CHAIN_LENGTH),
+            max_distance: self.max_distance,
+            lazy: self.lazy.unwrap_or(false),
+            optimal: self.optimal.unwrap_or(false),
+            checksum: self.checksum.unwrap_or(Checksum::Adler32),
+            high_water_mark: self.high_water_mark,
+            block_size: self.block_size,
+            grow_window: self.grow_window.unwrap_or(false),
+            store_only: self.store_only.unwrap_or(false),
+            strict_window: self.strict_window.unwrap_or(false),
+        }
+    }
+}
+
+/// The reasons a [`Config`] can be rejected.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum ConfigError {
+    /// The dictionary is larger than `u32::MAX` bytes.
+    DictTooLarge(usize),
+    /// The dictionary has no room for any bytes.
+    DictZero,
+    /// Every block would start over without a single byte in it.
+    BlockZero,
+    /// `min_match` is greater than `max_match`, so no match could ever be emitted.
+    MatchRange {
+        min_match: usize,
+        max_match: usize,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::DictTooLarge(dict_size) => write!(
+                f,
+                "Dictionary must be less than or equal to {} bytes, but is {} bytes!",
+                u32::MAX,
+                dict_size
+            ),
+            ConfigError::DictZero => write!(f, "Dictionary must not be empty!"),
+            ConfigError::BlockZero => write!(f, "Block size must not be zero!"),
+            ConfigError::MatchRange { min_match, max_match } => write!(
+                f,
+                "Minimum match length {} is greater than the maximum match length {}!",
+                min_match, max_match
+            ),
+        }
+    }
+}
+
+impl error::Error for ConfigError {}
+
+/// The reasons a [`Config`] can't be parsed from a string.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum ConfigParseError {
+    /// The key isn't the name of a field, `dict` or `level`.
+    UnknownKey(String),
+    /// The value is missing or isn't one the key can have.
+    InvalidValue { key: String, value: String },
+    /// Every key could be parsed, but the config they make up can't be used.
+    Invalid(ConfigError),
+}
+
+impl fmt::Display for ConfigParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigParseError::UnknownKey(key) => write!(f, "Unknown config key {:?}!", key),
+            ConfigParseError::InvalidValue { key, value } => {
+                write!(f, "Invalid value {:?} for config key {:?}!", value, key)
+            }
+            ConfigParseError::Invalid(err) => err.fmt(f),
+        }
+    }
+}
+
+impl error::Error for ConfigParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let parse = |s: &str| s.parse::<Config>();
+
+        assert_eq!(Ok(Config::default()), parse(""));
+        assert_eq!(Ok(Config::level(6)), parse("level=6"));
+        assert_eq!(
+            Ok(Config { dict_size: 0x1000, lazy: true, ..Config::level(2) }),
+            parse("dict=4096, lazy ,level=2")
+        );
+        assert_eq!(
+            Ok(Config {
+                checksum: Checksum::Crc32,
+                max_distance: Some(255),
+                block_size: Some(0x10000),
+                optimal: true,
+                ..Config::default()
+            }),
+            parse("checksum=crc32,max_distance=255,block_size=65536,optimal=true")
+        );
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let configs = [
+            Config::default(),
+            Config::level(0),
+            Config::level(9),
+            Config {
+                checksum: Checksum::None,
+                max_distance: Some(1),
+                high_water_mark: Some(0x400),
+                block_size: Some(0x100),
+                grow_window: true,
+                strict_window: true,
+                ..Config::level(3)
+            },
+        ];
+
+        for config in configs {
+            assert_eq!(Ok(config), config.to_string().parse());
+        }
+        assert_eq!(
+            "dict_size=4096,min_match=3,max_match=258,max_chain_length=0,lazy=false,\
+             optimal=false,grow_window=false,store_only=true,strict_window=false,checksum=adler32",
+            Config::level(0).to_string()
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = |s: &str| s.parse::<Config>().unwrap_err();
+
+        assert_eq!(ConfigParseError::UnknownKey("dictionary".into()), err("dictionary=4096"));
+        assert_eq!(
+            ConfigParseError::InvalidValue { key: "dict".into(), value: "big".into() },
+            err("dict=big")
+        );
+        assert_eq!(
+            ConfigParseError::InvalidValue { key: "level".into(), value: "".into() },
+            err("lazy,level")
+        );
+        assert_eq!(
+            ConfigParseError::InvalidValue { key: "checksum".into(), value: "md5".into() },
+            err("checksum=md5")
+        );
+        assert_eq!(ConfigParseError::Invalid(ConfigError::DictZero), err("dict=0"));
+        assert_eq!("Unknown config key \"lzma\"!", err("lzma").to_string());
+    }
+}
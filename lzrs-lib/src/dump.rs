@@ -0,0 +1,148 @@
+//! Human readable renderings of tokens, for debugging output.
+
+use std::fmt::{self, Display, Write};
+
+use crate::{ascii_char, Token};
+
+impl Display for Token {
+    /// Renders a literal as `<LIT 'a'>` and a rep as `<REP distance, length>`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Literal { byte } => write!(f, "<LIT '{}'>", ascii_char(*byte)),
+            Token::Rep { distance, length } => write!(f, "<REP {}, {}>", distance, length),
+        }
+    }
+}
+
+/// Renders a stream of tokens on one line. Runs of literals are quoted together and reps are
+/// written as `<distance,length>`, so `[a, b, rep 1 6]` is `"ab"<1,6>`.
+#[derive(Copy, Clone, Debug)]
+pub struct Tokens<'a>(pub &'a [Token]);
+
+impl Display for Tokens<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut in_literals = false;
+
+        for tok in self.0 {
+            match tok {
+                Token::Literal { byte } => {
+                    if !in_literals {
+                        f.write_char('"')?;
+                        in_literals = true;
+                    }
+                    f.write_str(&ascii_char(*byte))?;
+                }
+                Token::Rep { distance, length } => {
+                    if in_literals {
+                        f.write_char('"')?;
+                        in_literals = false;
+                    }
+                    write!(f, "<{},{}>", distance, length)?;
+                }
+            }
+        }
+
+        if in_literals {
+            f.write_char('"')?;
+        }
+        Ok(())
+    }
+}
+
+/// Lays the tokens out as a table with one token per row, giving its index, its kind and its
+/// contents.
+pub fn dump_tokens(tokens: &[Token]) -> String {
+    let width = tokens.len().saturating_sub(1).to_string().len();
+    let mut out = String::new();
+
+    for (i, tok) in tokens.iter().enumerate() {
+        // Writing to a `String` can't fail.
+        match tok {
+            Token::Literal { byte } => writeln!(
+                out,
+                "{:>width$}  LIT  {:<6} {:02x}",
+                i,
+                format!("'{}'", ascii_char(*byte)),
+                byte,
+                width = width
+            ),
+            Token::Rep { distance, length } => writeln!(
+                out,
+                "{:>width$}  REP  -{:<5} {}",
+                i,
+                distance,
+                length,
+                width = width
+            ),
+        }
+        .unwrap();
+    }
+    out
+}
+
+/// Renders bytes as the characters they stand for, like the literals of [`Tokens`] but unquoted.
+pub fn ascii_buf(buf: &[u8]) -> String {
+    buf.iter().map(|byte| ascii_char(*byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens() -> Vec<Token> {
+        let lit = |byte| Token::Literal { byte };
+        vec![
+            lit(b'a'),
+            lit(b'b'),
+            Token::Rep { distance: 1, length: 6 },
+            lit(b'\n'),
+            lit(b'\''),
+            lit(b'x'),
+            lit(b'y'),
+            lit(b'z'),
+            lit(0xff),
+            Token::Rep { distance: 10, length: 258 },
+            Token::Rep { distance: 0, length: 3 },
+        ]
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!("<LIT 'a'>", Token::Literal { byte: b'a' }.to_string());
+        assert_eq!("<LIT '\\n'>", Token::Literal { byte: b'\n' }.to_string());
+        assert_eq!("<REP 2, 3>", Token::Rep { distance: 2, length: 3 }.to_string());
+
+        assert_eq!(
+            r#""ab"<1,6>"\n\'xyz\xff"<10,258><0,3>"#,
+            Tokens(&tokens()).to_string()
+        );
+        assert_eq!("", Tokens(&[]).to_string());
+    }
+
+    #[test]
+    fn test_dump() {
+        let expected = [
+            " 0  LIT  'a'    61",
+            " 1  LIT  'b'    62",
+            " 2  REP  -1     6",
+            r" 3  LIT  '\n'   0a",
+            r" 4  LIT  '\''   27",
+            " 5  LIT  'x'    78",
+            " 6  LIT  'y'    79",
+            " 7  LIT  'z'    7a",
+            r" 8  LIT  '\xff' ff",
+            " 9  REP  -10    258",
+            "10  REP  -0     3",
+            "",
+        ]
+        .join("\n");
+        assert_eq!(expected, dump_tokens(&tokens()));
+        assert_eq!("", dump_tokens(&[]));
+    }
+
+    #[test]
+    fn test_ascii_buf() {
+        assert_eq!(r"ab c\n\'\xff", ascii_buf(b"ab c\n'\xff"));
+        assert_eq!("", ascii_buf(b""));
+    }
+}
@@ -0,0 +1,56 @@
+//! The frame around the token stream.
+//!
+//! A stream starts with a header, which tells the [`Reader`](crate::Reader) how to decode it:
+//!
+//! - 4 bytes of magic, `b"LZRS"`.
+//! - The format version, as a byte.
+//! - The [`Checksum`] in the trailer, as a byte.
+//! - The `dict_size` the stream was compressed with, as an LEB128 varint.
+//!
+//! The data follows as a sequence of blocks, each starting with a byte for its kind:
+//!
+//! - [`BLOCK_TOKENS`]: token groups (see `writer.rs`), ended by [`END`].
+//! - [`BLOCK_STORED`]: the length of the block as an LEB128 varint, followed by that many bytes
+//!   stored as they are. This is used wherever the tokens would take more space.
+//! - [`BLOCK_SYNC`]: an empty block, written by [`Writer::sync_flush`](crate::Writer::sync_flush)
+//!   to mark that everything before it can be decompressed on its own.
+//! - [`BLOCK_RESET`]: an empty block after which the window starts over empty, so that nothing
+//!   after it matches anything before it. This is where a [`Reader`](crate::Reader) can seek to.
+//! - [`BLOCK_END`]: there are no more blocks.
+//!
+//! The last block is followed by a trailer with the checksum of the uncompressed data as 4 big
+//! endian bytes. There is no trailer for [`Checksum::None`].
+
+use crate::varint::write_varint;
+use crate::{Checksum, Token};
+
+pub(crate) const MAGIC: [u8; 4] = *b"LZRS";
+
+/// The only format version so far.
+pub(crate) const VERSION: u8 = 1;
+
+/// Ends the blocks.
+pub(crate) const BLOCK_END: u8 = 0;
+/// A block of tokens.
+pub(crate) const BLOCK_TOKENS: u8 = 1;
+/// A block of bytes stored without compression.
+pub(crate) const BLOCK_STORED: u8 = 2;
+/// An empty block that marks a sync flush.
+pub(crate) const BLOCK_SYNC: u8 = 3;
+/// An empty block that clears the window.
+pub(crate) const BLOCK_RESET: u8 = 4;
+
+/// Marks the end of the tokens in a block. Matches are never empty, so this can't be confused
+/// with one.
+pub(crate) const END: Token = Token::Rep {
+    distance: 0,
+    length: 0,
+};
+
+/// Appends the header of a stream to the buffer.
+pub(crate) fn write_header(buf: &mut Vec<u8>, dict_size: usize, checksum: Checksum) {
+    buf.extend_from_slice(&MAGIC);
+    buf.push(VERSION);
+    buf.push(checksum as u8);
+    write_varint(buf, dict_size as u64);
+}
@@ -0,0 +1,110 @@
+//! A rolling hash over a sliding window of bytes.
+
+/// The rolling checksum from rsync, which is Adler-32 without the modulus or the initial 1. The
+/// hash of a window is updated in O(1) as it slides by one byte, with [`RollingHash::roll`].
+///
+/// ```
+/// use lzrs_lib::hash::RollingHash;
+///
+/// let mut rolled = RollingHash::new();
+/// for &byte in b"abc" {
+///     rolled.push(byte);
+/// }
+/// rolled.roll(b'a', b'd');
+///
+/// let mut pushed = RollingHash::new();
+/// for &byte in b"bcd" {
+///     pushed.push(byte);
+/// }
+/// assert_eq!(pushed.value(), rolled.value());
+/// ```
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub struct RollingHash {
+    /// The sum of the bytes in the window.
+    a: u32,
+    /// The sum of each byte weighted by how far it is from the end of the window, counting from 1.
+    b: u32,
+    /// The number of bytes in the window.
+    len: u32,
+}
+
+impl RollingHash {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Grows the window by adding `byte` at its end.
+    pub fn push(&mut self, byte: u8) {
+        self.a = self.a.wrapping_add(byte as u32);
+        self.b = self.b.wrapping_add(self.a);
+        self.len = self.len.wrapping_add(1);
+    }
+
+    /// Slides the window by one byte, removing `out_byte` from its start and adding `in_byte` at
+    /// its end. `out_byte` must be the first byte of the window, or the hash is meaningless.
+    pub fn roll(&mut self, out_byte: u8, in_byte: u8) {
+        self.a = self.a.wrapping_sub(out_byte as u32).wrapping_add(in_byte as u32);
+        self.b = self
+            .b
+            .wrapping_sub(self.len.wrapping_mul(out_byte as u32))
+            .wrapping_add(self.a);
+    }
+
+    /// The number of bytes in the window.
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The hash of the window, with both sums taken modulo 2^16.
+    pub fn value(&self) -> u32 {
+        (self.b << 16) | (self.a & 0xffff)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::cycled;
+
+    fn pushed(bytes: &[u8]) -> RollingHash {
+        let mut hash = RollingHash::new();
+        for &byte in bytes {
+            hash.push(byte);
+        }
+        hash
+    }
+
+    #[test]
+    fn test_roll() {
+        let input = cycled(b"Hey, banana-ass! To banana or not to banana? \xff\xfe\x00\x80", 0x400);
+
+        for len in [1, 3, 16, 255] {
+            let mut rolled = pushed(&input[..len]);
+            for start in 1..input.len() - len {
+                rolled.roll(input[start - 1], input[start + len - 1]);
+                assert_eq!(
+                    pushed(&input[start..start + len]).value(),
+                    rolled.value(),
+                    "The hash of {} bytes at {} rolled wrong.",
+                    len,
+                    start
+                );
+                assert_eq!(len, rolled.len());
+            }
+        }
+    }
+
+    #[test]
+    fn test_value() {
+        assert_eq!(0, RollingHash::new().value());
+        assert!(RollingHash::new().is_empty());
+
+        // a = 97 + 98 + 99, b = 3 * 97 + 2 * 98 + 99.
+        assert_eq!((586 << 16) | 294, pushed(b"abc").value());
+        assert_ne!(pushed(b"abc").value(), pushed(b"cba").value());
+    }
+}
@@ -1,12 +1,37 @@
+mod checksum;
+mod config;
+mod dump;
+mod frame;
+pub mod hash;
+mod match_finder;
+mod reader;
+mod stats;
+mod stream;
+#[cfg(test)]
+mod test_util;
+mod trace;
+pub mod util;
+mod varint;
 mod writer;
-pub use writer::Writer;
-
-pub struct Config {
-    pub dict_size: usize,
-}
+pub use checksum::Checksum;
+pub use config::{Config, ConfigBuilder, ConfigError, ConfigParseError};
+pub use dump::{ascii_buf, dump_tokens, Tokens};
+pub use match_finder::{HashChain, MatchFinder};
+pub use reader::{Reader, TokenReader};
+pub use stats::Stats;
+pub use stream::{compress, compress_to_vec, decompress, decompress_to_vec};
+pub use writer::{SeekPoint, Writer};
+pub use lzrs2::buffer::{ringbuf::RingBuf, vecbuf::VecBuf, Buffer};
 
+/// A single token of a compressed stream.
+///
+/// With the `serde` feature, tokens serialize tagged by a `t` of `"lit"` or `"rep"`, so a
+/// literal is `{"t":"lit","byte":97}`.
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "t", rename_all = "lowercase"))]
 pub enum Token {
+    #[cfg_attr(feature = "serde", serde(rename = "lit"))]
     Literal {
         byte: u8,
     },
@@ -16,7 +41,27 @@ pub enum Token {
     }
 }
 
+impl Token {
+    /// The number of bytes the token takes in a block of tokens, not counting its bit of the
+    /// control byte: the byte itself for a literal, and the varints of its `distance` and
+    /// `length` for a rep.
+    pub fn encoded_len(&self) -> usize {
+        match *self {
+            Token::Literal { .. } => 1,
+            Token::Rep { distance, length } => {
+                varint::varint_len(distance as u64) + varint::varint_len(length as u64)
+            }
+        }
+    }
+}
+
+/// Renders a byte as the character it stands for, escaping anything that isn't printable ASCII.
+pub(crate) fn ascii_char(byte: u8) -> String {
+    std::ascii::escape_default(byte).to_string()
+}
+
 pub mod prelude {
-    pub use super::writer::Writer;
-    pub use super::Config;
+    pub use super::reader::{Reader, TokenReader};
+    pub use super::writer::{SeekPoint, Writer};
+    pub use super::{Checksum, Config, ConfigBuilder, ConfigError, ConfigParseError, Stats};
 }
@@ -0,0 +1,487 @@
+//! The strategies the [`Writer`](crate::Writer) finds matches in its window with.
+
+use std::io;
+
+use lzrs2::buffer::Distance;
+
+use crate::{Buffer, Config};
+
+/// The number of bytes that are hashed to find candidate matches.
+pub(crate) const HASH_LEN: usize = 3;
+
+/// Finds the longest match for the lookahead among the positions inserted into it.
+///
+/// Positions are virtual indices into the window, counting every byte ever written into it, so
+/// they are inserted in order starting from 0. The window itself is kept by the writer and passed
+/// to [`MatchFinder::find`].
+pub trait MatchFinder {
+    fn with_config(config: &Config) -> Self;
+
+    /// Inserts the position `pos`, whose byte is the first of `bytes`. The rest of `bytes` is
+    /// whatever is known to follow it, which may be nothing.
+    fn insert(&mut self, pos: usize, bytes: &[u8]);
+
+    /// Inserts the `len` positions from `pos` on, whose bytes start at the start of `bytes`, the
+    /// same as inserting each of them in turn.
+    fn insert_many(&mut self, pos: usize, bytes: &[u8], len: usize) {
+        for i in 0..len {
+            self.insert(pos + i, &bytes[i..]);
+        }
+    }
+
+    /// Returns the `(distance, length)` of the longest match for the start of the lookahead, or
+    /// `None` if there is none. Of matches that are equally long, the nearest one is returned,
+    /// since its distance takes the fewest bytes. The length is capped at `max_match`, the
+    /// distance at `max_distance`, and the match can overlap the lookahead.
+    ///
+    /// Fails with [`io::ErrorKind::Other`] if a position the finder has doesn't match the window,
+    /// which means the finder and the window have been written out of step.
+    fn find<B: Buffer>(&self, window: &B, lookahead: &[u8]) -> io::Result<Option<(usize, usize)>>;
+
+    /// Finds a match like [`MatchFinder::find`], along with the number of candidate positions
+    /// visited to find it, which is 0 for finders without chains to follow.
+    fn find_with_hops<B: Buffer>(
+        &self,
+        window: &B,
+        lookahead: &[u8],
+    ) -> io::Result<(Option<(usize, usize)>, usize)> {
+        Ok((self.find(window, lookahead)?, 0))
+    }
+
+    /// Forgets every position, for a new stream.
+    fn reset(&mut self);
+}
+
+/// Returns the length of the match for the lookahead starting at the position `at`, capped at
+/// `max_match`. The match can run past the head into the lookahead itself.
+pub(crate) fn match_len<B: Buffer>(
+    window: &B,
+    at: usize,
+    lookahead: &[u8],
+    max_match: usize,
+) -> io::Result<usize> {
+    let max_len = lookahead.len().min(max_match);
+    Ok(window.match_length_at(Distance::new(distance(window, at)?), &lookahead[..max_len]))
+}
+
+/// The number of positions back from the head that matches can be found at, which is the whole
+/// dictionary unless `max_distance` allows less.
+pub(crate) fn reach(config: &Config) -> usize {
+    match config.max_distance {
+        Some(max_distance) => config.dict_size.min(max_distance.saturating_add(1)),
+        None => config.dict_size,
+    }
+}
+
+/// Returns the position as the distance from the head, where 0 distance means the last byte
+/// added to the window. Matches are only ever found at positions still in the window, so any
+/// other position is an error.
+fn distance<B: Buffer>(window: &B, pos: usize) -> io::Result<usize> {
+    match window.distance_of(pos) {
+        Some(distance) => Ok(distance.get()),
+        None => Err(io::Error::other(format!(
+            "Position {} is not in the window of {} bytes.",
+            pos,
+            window.len()
+        ))),
+    }
+}
+
+/// Finds matches through chains of the positions that share a hash of their first `HASH_LEN`
+/// bytes, visiting at most `max_chain_length` of them. Positions that are followed by fewer than
+/// `HASH_LEN` known bytes when inserted can't be found, except for the last one, which is always
+/// tried for a run.
+#[derive(Debug)]
+pub struct HashChain {
+    dict_size: usize,
+    reach: usize,
+    max_match: usize,
+    max_chain_length: usize,
+
+    /// The position after the last one inserted.
+    pos: usize,
+
+    /// The most recent position for each hash. Positions count every byte ever inserted, so that
+    /// overwritten positions can be told apart.
+    map: Vec<usize>,
+    /// The previous position with the same hash as the position at each dictionary index.
+    chain: Vec<usize>,
+    hash_shift: u32,
+}
+
+impl HashChain {
+    /// Returns the position of the next candidate match after `last`, or the first one if `last`
+    /// is `None`. Candidates share a hash with the lookahead, but aren't guaranteed to match it.
+    pub(crate) fn next_match_pos(&self, last: Option<usize>, lookahead: &[u8]) -> Option<usize> {
+        let pos = if let Some(last) = last {
+            self.chain[last % self.dict_size]
+        } else {
+            self.map[self.hash(lookahead)?]
+        };
+
+        // Positions more than `dict_size` behind have been overwritten since they were linked, and
+        // the chain only gets further back from here once it is out of reach.
+        if pos < self.pos && self.pos - pos <= self.reach {
+            Some(pos)
+        } else {
+            None
+        }
+    }
+
+    /// Hashes the first `HASH_LEN` bytes, or returns `None` if there are fewer than that.
+    fn hash(&self, bytes: &[u8]) -> Option<usize> {
+        if bytes.len() < HASH_LEN {
+            return None;
+        }
+        Some(hash(bytes, self.hash_shift))
+    }
+
+    /// Makes `pos` the most recent position for the hash of `bytes`, returning the one it
+    /// replaces, which is what `pos` links to in the chain.
+    fn link(&mut self, pos: usize, bytes: &[u8]) -> usize {
+        match self.hash(bytes) {
+            Some(hash) => std::mem::replace(&mut self.map[hash], pos),
+            None => usize::MAX,
+        }
+    }
+}
+
+/// Hashes the first `HASH_LEN` bytes, of which there have to be at least that many.
+fn hash(bytes: &[u8], hash_shift: u32) -> usize {
+    let v = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0]);
+    (v.wrapping_mul(0x9e37_79b1) >> hash_shift) as usize
+}
+
+impl MatchFinder for HashChain {
+    fn with_config(config: &Config) -> Self {
+        // One hash bucket per dictionary byte, within reason.
+        let hash_bits = config.dict_size.next_power_of_two().trailing_zeros().clamp(8, 16);
+
+        Self {
+            dict_size: config.dict_size,
+            reach: reach(config),
+            max_match: config.max_match,
+            max_chain_length: config.max_chain_length,
+            pos: 0,
+            map: vec![usize::MAX; 1 << hash_bits],
+            chain: Vec::with_capacity(config.dict_size),
+            hash_shift: 32 - hash_bits,
+        }
+    }
+
+    fn insert(&mut self, pos: usize, bytes: &[u8]) {
+        let first_match = self.link(pos, bytes);
+
+        if self.chain.len() < self.dict_size {
+            self.chain.push(first_match);
+        } else {
+            self.chain[pos % self.dict_size] = first_match;
+        }
+
+        self.pos = pos + 1;
+    }
+
+    /// Links the positions a span of the chain at a time, split where the chain wraps around, so
+    /// that the index into it only has to be found once per span.
+    fn insert_many(&mut self, pos: usize, bytes: &[u8], len: usize) {
+        // Until the chain is full, every position is pushed onto its end.
+        let mut i = 0;
+        while i < len && self.chain.len() < self.dict_size {
+            let first_match = self.link(pos + i, &bytes[i..]);
+            self.chain.push(first_match);
+            i += 1;
+        }
+
+        // Only the positions followed by `HASH_LEN` bytes are hashed.
+        let hashed = bytes.len().saturating_sub(HASH_LEN - 1).min(len);
+        while i < len {
+            let start = (pos + i) % self.dict_size;
+            let span = (len - i).min(self.dict_size - start);
+
+            for (j, link) in self.chain[start..start + span].iter_mut().enumerate() {
+                let at = i + j;
+                *link = if at < hashed {
+                    std::mem::replace(&mut self.map[hash(&bytes[at..], self.hash_shift)], pos + at)
+                } else {
+                    usize::MAX
+                };
+            }
+            i += span;
+        }
+
+        if len > 0 {
+            self.pos = pos + len;
+        }
+    }
+
+    fn find<B: Buffer>(&self, window: &B, lookahead: &[u8]) -> io::Result<Option<(usize, usize)>> {
+        Ok(self.find_with_hops(window, lookahead)?.0)
+    }
+
+    fn find_with_hops<B: Buffer>(
+        &self,
+        window: &B,
+        lookahead: &[u8],
+    ) -> io::Result<(Option<(usize, usize)>, usize)> {
+        // The length and position of the best match so far. On equal lengths, the later position
+        // is nearer, so it compares greater.
+        let mut best_match = (0, None);
+
+        // A run of the last byte, at distance 0, is tried first. Its position can't be found
+        // through the hashes if it was too close to the end of the lookahead to be hashed, such
+        // as at a flush or at the end of a preset dictionary.
+        if self.pos > 0 {
+            let len = match_len(window, self.pos - 1, lookahead, self.max_match)?;
+            best_match = (len, Some(self.pos - 1));
+        }
+
+        // A chain can't visit more positions than the dictionary holds, so it must have a cycle if
+        // it goes on for longer.
+        let max_chain_length = self.max_chain_length.min(self.dict_size);
+
+        let mut last = None;
+        let mut chain_length = 0;
+        while chain_length < max_chain_length {
+            let match_pos = match self.next_match_pos(last, lookahead) {
+                Some(match_pos) => match_pos,
+                None => break,
+            };
+            let len = match_len(window, match_pos, lookahead, self.max_match)?;
+
+            if (len, Some(match_pos)) > best_match {
+                best_match = (len, Some(match_pos))
+            }
+
+            last = Some(match_pos);
+            chain_length += 1;
+        }
+
+        let found = match best_match {
+            (len, Some(pos)) if len > 0 => {
+                // Every candidate is at most `reach` positions back, so its distance is less.
+                let distance = distance(window, pos)?;
+                debug_assert!(distance < self.reach, "Distance {} is out of reach.", distance);
+                Some((distance, len))
+            }
+            _ => None,
+        };
+        Ok((found, chain_length))
+    }
+
+    fn reset(&mut self) {
+        self.pos = 0;
+        self.map.fill(usize::MAX);
+        self.chain.clear();
+    }
+}
+
+/// Tries every position in the window, most recent first. This is far too slow to compress with,
+/// but always finds the longest match, so it is only built for testing the other finders against.
+#[cfg(test)]
+#[derive(Debug)]
+pub(crate) struct BruteForce {
+    reach: usize,
+    max_match: usize,
+
+    /// The position after the last one inserted.
+    pos: usize,
+}
+
+#[cfg(test)]
+impl MatchFinder for BruteForce {
+    fn with_config(config: &Config) -> Self {
+        Self {
+            reach: reach(config),
+            max_match: config.max_match,
+            pos: 0,
+        }
+    }
+
+    fn insert(&mut self, pos: usize, _bytes: &[u8]) {
+        self.pos = pos + 1;
+    }
+
+    fn find<B: Buffer>(&self, window: &B, lookahead: &[u8]) -> io::Result<Option<(usize, usize)>> {
+        let mut best_match = None;
+        let mut best_len = 0;
+
+        // The nearest match is found first, so only a longer one replaces it.
+        for distance in 0..self.pos.min(self.reach) {
+            let len = match_len(window, self.pos - 1 - distance, lookahead, self.max_match)?;
+            if len > best_len {
+                best_match = Some((distance, len));
+                best_len = len;
+            }
+        }
+        Ok(best_match)
+    }
+
+    fn reset(&mut self) {
+        self.pos = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{cycled, random_bytes, SEED};
+    use crate::RingBuf;
+    use std::io::Write;
+
+    fn config() -> Config {
+        Config::builder()
+            .dict_size(0x80)
+            .max_chain_length(usize::MAX)
+            .build()
+    }
+
+    /// Creates a finder and a window with `bytes` inserted into both.
+    fn primed<F: MatchFinder>(config: &Config, bytes: &[u8]) -> (F, RingBuf) {
+        let mut finder = F::with_config(config);
+        let mut window = RingBuf::with_capacity(config.dict_size);
+        for i in 0..bytes.len() {
+            finder.insert(i, &bytes[i..]);
+        }
+        window.write_all(bytes).unwrap();
+        (finder, window)
+    }
+
+    #[test]
+    fn test_same_longest_match() {
+        let bytes = b"Hey, banana-ass! To banana or not to banana? It's a banana-ass's banana.";
+        let lookaheads: [&[u8]; 7] = [
+            b"banana or not",
+            b"banana-ass's",
+            b"To banana!",
+            b"ass!",
+            b"...",
+            b"nanana!",
+            b"Hey, banana-ass! To banana or not to banana? It's a banana-ass's banana.",
+        ];
+
+        let (hash_chain, window) = primed::<HashChain>(&config(), bytes);
+        let (brute_force, _) = primed::<BruteForce>(&config(), bytes);
+        for lookahead in lookaheads {
+            assert_eq!(
+                brute_force.find(&window, lookahead).unwrap(),
+                hash_chain.find(&window, lookahead).unwrap(),
+                "The finders disagree on {:?}.",
+                String::from_utf8_lossy(lookahead)
+            );
+        }
+
+        // Matches shorter than `HASH_LEN` are only found by brute force, unless they are runs.
+        assert_eq!(None, hash_chain.find(&window, b"aaaa").unwrap());
+        assert_eq!(Some((1, 1)), brute_force.find(&window, b"aaaa").unwrap());
+        assert_eq!(Some((0, 1)), hash_chain.find(&window, b".!").unwrap());
+    }
+
+    #[test]
+    fn test_nearest_match() {
+        // "abcd" matches at distances 12 and 3, and "abc" at 7 as well.
+        let bytes = b"abcd-abc-abcd";
+        let (hash_chain, window) = primed::<HashChain>(&config(), bytes);
+        let (brute_force, _) = primed::<BruteForce>(&config(), bytes);
+        assert_eq!(Some((3, 4)), hash_chain.find(&window, b"abcdX").unwrap());
+        assert_eq!(Some((3, 4)), brute_force.find(&window, b"abcdX").unwrap());
+        assert_eq!(Some((3, 3)), hash_chain.find(&window, b"abcX").unwrap());
+        assert_eq!(Some((3, 3)), brute_force.find(&window, b"abcX").unwrap());
+    }
+
+    #[test]
+    fn test_out_of_step() {
+        let (hash_chain, _) = primed::<HashChain>(&config(), b"abcabc");
+        let (brute_force, _) = primed::<BruteForce>(&config(), b"abcabc");
+
+        // The positions were never written into this window.
+        let window = RingBuf::with_capacity(0x80);
+        for err in [hash_chain.find(&window, b"abc"), brute_force.find(&window, b"abc")] {
+            assert_eq!(io::ErrorKind::Other, err.unwrap_err().kind());
+        }
+    }
+
+    #[test]
+    fn test_window_limit() {
+        // Both only reach back `dict_size` bytes, even though the ring buffer holds more.
+        let small = Config { dict_size: 12, ..config() };
+        let bytes = b"abcdefgh-0123456789";
+        let (hash_chain, window) = primed::<HashChain>(&small, bytes);
+        let (brute_force, _) = primed::<BruteForce>(&small, bytes);
+
+        assert_eq!(None, hash_chain.find(&window, b"abcd").unwrap());
+        assert_eq!(None, brute_force.find(&window, b"abcd").unwrap());
+        assert_eq!(Some((9, 4)), hash_chain.find(&window, b"0123").unwrap());
+        assert_eq!(Some((9, 4)), brute_force.find(&window, b"0123").unwrap());
+    }
+
+    #[test]
+    fn test_insert_many() {
+        let bytes = cycled(b"To banana or not to banana? ", 0x100);
+
+        // Spans that fill the chain, wrap it, and stop short of the end of the bytes.
+        for dict_size in [16, 0x30, 0x80, 0x200] {
+            let config = Config { dict_size, ..config() };
+            let mut each = HashChain::with_config(&config);
+            let mut many = HashChain::with_config(&config);
+
+            let mut pos = 0;
+            let spans = [(0, 3), (5, 5), (1, 1), (40, 42), (17, 30), (0x50, 0x50), (23, 24)];
+            for (len, known) in spans {
+                let lookahead = &bytes[pos..pos + known];
+                for i in 0..len {
+                    each.insert(pos + i, &lookahead[i..]);
+                }
+                many.insert_many(pos, lookahead, len);
+                pos += len;
+
+                assert_eq!(each.pos, many.pos);
+                assert_eq!(each.map, many.map);
+                assert_eq!(each.chain, many.chain);
+            }
+        }
+    }
+
+    #[test]
+    fn test_longest_match_corpus() {
+        // Random bytes with only a few values, so that there is little to match.
+        let random: Vec<u8> = random_bytes(0x200, SEED).iter().map(|b| b & 0x7).collect();
+        let text = cycled(b"Hey, banana-ass! To banana or not to banana? ", 0x200);
+        let runs: Vec<u8> = (0..0x200).map(|i: usize| (i / 7 % 3) as u8).collect();
+
+        // The small dictionary wraps, so stale positions are left in the chains.
+        let configs = [
+            config(),
+            Config { dict_size: 16, ..config() },
+            Config { max_distance: Some(20), ..config() },
+        ];
+        for config in configs {
+            for input in [&random, &text, &runs] {
+                let mut hash_chain = HashChain::with_config(&config);
+                let mut brute_force = BruteForce::with_config(&config);
+                let mut window = RingBuf::with_capacity(config.dict_size);
+
+                for i in 0..input.len() {
+                    let lookahead = &input[i..];
+                    let len = |found: Option<(usize, usize)>| found.map_or(0, |(_, len)| len);
+                    let best = len(brute_force.find(&window, lookahead).unwrap());
+                    let found = len(hash_chain.find(&window, lookahead).unwrap());
+
+                    // Shorter matches can't be hashed, but they may still be found.
+                    if best >= HASH_LEN {
+                        assert_eq!(
+                            best, found,
+                            "The hash chains missed the longest match at {}.",
+                            i
+                        );
+                    } else {
+                        assert!(found <= best);
+                    }
+
+                    hash_chain.insert(i, lookahead);
+                    brute_force.insert(i, lookahead);
+                    window.write_all(&lookahead[..1]).unwrap();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,800 @@
+//! The decompressing [`Reader`], which decodes the format produced by the
+//! [`Writer`](crate::Writer).
+
+use std::io::{self, Read, Result, Seek, SeekFrom};
+
+use crate::checksum::{Checksum, Hasher};
+use crate::frame::{
+    BLOCK_END, BLOCK_RESET, BLOCK_STORED, BLOCK_SYNC, BLOCK_TOKENS, END, MAGIC, VERSION,
+};
+use crate::varint::{self, MAX_LEN};
+use crate::{SeekPoint, Token};
+
+/// The size of the chunks read from the inner reader.
+const READ_BUF_SIZE: usize = 0x1000;
+
+/// The size of the chunks decoded and thrown away by [`Reader::skip`].
+const SKIP_BUF_SIZE: usize = 0x400;
+
+/// The kind of block being decoded.
+#[derive(Copy, Clone, Debug)]
+enum Block {
+    /// The next byte starts a block.
+    None,
+    Tokens,
+    /// A stored block with this many bytes left.
+    Stored(usize),
+}
+
+/// Decompresses a stream read from the inner reader. The window is sized by the stream's header.
+#[derive(Debug)]
+pub struct Reader<R> {
+    /// Whether the header has been read, which sets `dict_size`.
+    header_read: bool,
+    dict_size: usize,
+
+    inner: R,
+    read_buf: Vec<u8>,
+    read_pos: usize,
+
+    block: Block,
+    /// The control byte of the current token group.
+    ctrl: u8,
+    /// The bit in the control byte describing the next token. A new control byte is read when
+    /// this is 0.
+    ctrl_bit: u8,
+
+    /// The output window that `Token::Rep`s copy from.
+    dict: Vec<u8>,
+    head: usize,
+    /// The preset dictionary, which is moved into the window once the header sizes it.
+    preset: Vec<u8>,
+
+    /// The `(distance, length)` of the `Token::Rep` currently being copied out.
+    rep: Option<(usize, usize)>,
+
+    /// The checksum of everything read so far, of the kind given by the header.
+    checksum: Hasher,
+    /// Whether the checksum is checked against the trailer, which it can't be once part of the
+    /// stream has been skipped by seeking.
+    verify: bool,
+    /// Whether the last block has been read.
+    end: bool,
+    /// The checksum from the trailer, if the stream has one and the end has been reached.
+    trailer: Option<u32>,
+}
+
+impl<R: Read> Read for Reader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let mut filled = 0;
+        let mut ended = false;
+
+        while filled < buf.len() {
+            if let Some((distance, length)) = self.rep {
+                buf[filled] = self.copy_from_dictionary(distance);
+                filled += 1;
+
+                self.rep = if length > 1 {
+                    Some((distance, length - 1))
+                } else {
+                    None
+                };
+                continue;
+            }
+
+            match self.next_token()? {
+                Some(Token::Literal { byte }) => {
+                    self.write_to_dictionary(byte);
+                    buf[filled] = byte;
+                    filled += 1;
+                }
+                Some(Token::Rep { distance, length }) => {
+                    // The history is only what is in the window, which holds at most `dict_size`
+                    // bytes and starts over empty after a reset block. The rep may still overlap
+                    // its own output, however long it is.
+                    if distance >= self.dict.len() {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!(
+                                "Rep distance {} is outside of the {} bytes of history.",
+                                distance,
+                                self.dict.len()
+                            ),
+                        ));
+                    }
+
+                    self.rep = Some((distance, length));
+                }
+                None => {
+                    ended = true;
+                    break;
+                }
+            }
+        }
+
+        self.checksum.update(&buf[..filled]);
+        if ended {
+            if !self.end && filled == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "Stream ended before the end marker.",
+                ));
+            }
+            if let (Some(trailer), Some(checksum)) = (self.trailer, self.checksum.finish()) {
+                if self.verify && trailer != checksum {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "Checksum {:08x} does not match the trailer {:08x}.",
+                            checksum, trailer
+                        ),
+                    ));
+                }
+            }
+        }
+
+        Ok(filled)
+    }
+}
+
+impl<R: Read> Reader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            header_read: false,
+            dict_size: 0,
+            inner,
+            read_buf: vec![],
+            read_pos: 0,
+            block: Block::None,
+            ctrl: 0,
+            ctrl_bit: 0,
+            dict: vec![],
+            head: 0,
+            preset: vec![],
+            rep: None,
+            checksum: Hasher::new(Checksum::None),
+            verify: true,
+            end: false,
+            trailer: None,
+        }
+    }
+
+    /// Reads and validates the header, sizing the window from it.
+    fn read_header(&mut self) -> Result<()> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+        let mut magic = [0; 4];
+        for byte in &mut magic {
+            *byte = self.next_byte_or_eof()?;
+        }
+        if magic != MAGIC {
+            return Err(invalid(format!("Bad magic {:x?}.", magic)));
+        }
+
+        let version = self.next_byte_or_eof()?;
+        if version != VERSION {
+            return Err(invalid(format!("Unsupported version {}.", version)));
+        }
+
+        let checksum = self.next_byte_or_eof()?;
+        match Checksum::from_byte(checksum) {
+            Some(checksum) => self.checksum = Hasher::new(checksum),
+            None => return Err(invalid(format!("Unknown checksum {}.", checksum))),
+        }
+
+        let dict_size = self.read_varint()?;
+        if dict_size == 0 || dict_size > u32::MAX as u64 {
+            return Err(invalid(format!("Invalid dictionary size {}.", dict_size)));
+        }
+
+        self.dict_size = dict_size as usize;
+        self.header_read = true;
+
+        let preset = std::mem::take(&mut self.preset);
+        for &byte in &preset[preset.len().saturating_sub(self.dict_size)..] {
+            self.write_to_dictionary(byte);
+        }
+        Ok(())
+    }
+
+    /// Primes the window with a preset dictionary, which has to be the same one the stream was
+    /// compressed with. See [`Writer::set_dictionary`](crate::Writer::set_dictionary). Panics if
+    /// anything has already been read.
+    pub fn set_dictionary(&mut self, dict: &[u8]) {
+        assert!(!self.header_read, "The dictionary must be set before anything is read.");
+        self.preset = dict.to_vec();
+    }
+
+    /// Decodes the next `n` bytes of output and throws them away, returning how many there were,
+    /// which is fewer than `n` only at the end of the stream. Everything skipped still goes into
+    /// the window and the checksum, so the bytes after it decode and verify the same as if they
+    /// had all been read.
+    pub fn skip(&mut self, n: usize) -> Result<usize> {
+        let mut buf = [0; SKIP_BUF_SIZE];
+        let mut skipped = 0;
+        while skipped < n {
+            let len = (n - skipped).min(SKIP_BUF_SIZE);
+            match self.read(&mut buf[..len]) {
+                Ok(0) => break,
+                Ok(read) => skipped += read,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(skipped)
+    }
+
+    /// Decodes the next token, or returns `None` at the end of the tokens. They end either with
+    /// the last block, which also reads the trailer, or with the stream ending on a token
+    /// boundary. The bytes of stored blocks are decoded as literals.
+    pub(crate) fn next_token(&mut self) -> Result<Option<Token>> {
+        if !self.header_read {
+            self.read_header()?;
+        }
+
+        loop {
+            if self.end {
+                return Ok(None);
+            }
+
+            match self.block {
+                Block::None => {
+                    let kind = match self.next_byte()? {
+                        Some(kind) => kind,
+                        None => return Ok(None),
+                    };
+                    self.read_block_header(kind)?;
+                }
+                Block::Stored(0) => self.block = Block::None,
+                Block::Stored(len) => {
+                    self.block = Block::Stored(len - 1);
+                    return Ok(self.next_byte()?.map(|byte| Token::Literal { byte }));
+                }
+                Block::Tokens => match self.next_block_token()? {
+                    // The end marker only ends the block.
+                    Some(END) => self.block = Block::None,
+                    tok => return Ok(tok),
+                },
+            }
+        }
+    }
+
+    /// Starts the block of the given kind, skips a sync block, clears the window for a reset
+    /// block, or reads the trailer after the last block.
+    fn read_block_header(&mut self, kind: u8) -> Result<()> {
+        match kind {
+            BLOCK_TOKENS => {
+                self.block = Block::Tokens;
+                self.ctrl_bit = 0;
+            }
+            BLOCK_STORED => self.block = Block::Stored(self.read_varint()? as usize),
+            // There is nothing in a sync block.
+            BLOCK_SYNC => {}
+            BLOCK_RESET => {
+                self.dict.clear();
+                self.head = 0;
+            }
+            BLOCK_END => {
+                if self.checksum.kind() != Checksum::None {
+                    let mut trailer = [0; 4];
+                    for byte in &mut trailer {
+                        *byte = self.next_byte_or_eof()?;
+                    }
+                    self.trailer = Some(u32::from_be_bytes(trailer));
+                }
+                self.end = true;
+            }
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Unknown block kind {}.", kind),
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    /// Decodes the next token of a block of tokens, including the end marker. Returns `None` if
+    /// the stream ends on a token boundary.
+    fn next_block_token(&mut self) -> Result<Option<Token>> {
+        if self.ctrl_bit == 0 {
+            match self.next_byte()? {
+                Some(ctrl) => self.ctrl = ctrl,
+                None => return Ok(None),
+            }
+        }
+
+        let is_rep = self.ctrl & (1 << self.ctrl_bit) != 0;
+        self.ctrl_bit = (self.ctrl_bit + 1) % 8;
+
+        let tok = if is_rep {
+            let distance = self.read_varint()? as usize;
+            let length = self.read_varint()? as usize;
+            Token::Rep { distance, length }
+        } else {
+            match self.next_byte()? {
+                Some(byte) => Token::Literal { byte },
+                None => return Ok(None),
+            }
+        };
+
+        Ok(Some(tok))
+    }
+
+    /// Reads the next byte of the compressed stream, or returns `None` at the end of the stream.
+    fn next_byte(&mut self) -> Result<Option<u8>> {
+        if self.read_pos == self.read_buf.len() {
+            self.read_buf.resize(READ_BUF_SIZE, 0);
+            let read = loop {
+                match self.inner.read(&mut self.read_buf) {
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                    res => break res?,
+                }
+            };
+            self.read_buf.truncate(read);
+            self.read_pos = 0;
+
+            if read == 0 {
+                return Ok(None);
+            }
+        }
+
+        let byte = self.read_buf[self.read_pos];
+        self.read_pos += 1;
+        Ok(Some(byte))
+    }
+
+    /// Reads the next byte, failing if the stream has ended.
+    fn next_byte_or_eof(&mut self) -> Result<u8> {
+        self.next_byte()?
+            .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))
+    }
+
+    /// Reads an LEB128 varint, failing if the stream ends before it is complete.
+    fn read_varint(&mut self) -> Result<u64> {
+        // Most varints are already buffered whole, so they don't need to go byte by byte.
+        if let Some(v) = varint::read_varint(&self.read_buf, &mut self.read_pos) {
+            return Ok(v);
+        }
+        if self.read_buf.len() - self.read_pos >= MAX_LEN {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Varint is too long."));
+        }
+
+        let mut v = 0;
+        let mut shift = 0;
+
+        loop {
+            let byte = self.next_byte_or_eof()?;
+            if shift >= 64 {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "Varint is too long."));
+            }
+            v |= ((byte & 0x7f) as u64) << shift;
+
+            if byte & 0x80 == 0 {
+                return Ok(v);
+            }
+            shift += 7;
+        }
+    }
+
+    /// Copies the byte at `distance` from the head to the head of the dictionary, returning it.
+    fn copy_from_dictionary(&mut self, distance: usize) -> u8 {
+        let index = if self.head > distance {
+            self.head - distance - 1
+        } else {
+            self.dict_size - distance + self.head - 1
+        };
+
+        let byte = self.dict[index];
+        self.write_to_dictionary(byte);
+        byte
+    }
+
+    fn write_to_dictionary(&mut self, byte: u8) {
+        if self.dict.len() < self.dict_size {
+            self.dict.push(byte);
+        } else {
+            self.dict[self.head] = byte;
+        }
+
+        self.head = (self.head + 1) % self.dict_size;
+    }
+}
+
+impl<R: Read + Seek> Reader<R> {
+    /// Jumps to the start of a block recorded by
+    /// [`Writer::seek_points`](crate::Writer::seek_points), so that the next read returns the
+    /// input from [`SeekPoint::input`] on. The stream has to start at the start of the inner
+    /// reader, and the header is read from there first if it hasn't been yet. Since the bytes
+    /// before the block are never decoded, the checksum in the trailer is no longer verified.
+    ///
+    /// Fails with [`io::ErrorKind::InvalidInput`] if no block starts at the point.
+    pub fn seek_block(&mut self, point: SeekPoint) -> Result<()> {
+        if !self.header_read {
+            self.read_header()?;
+        }
+
+        self.inner.seek(SeekFrom::Start(point.output))?;
+        self.read_buf.clear();
+        self.read_pos = 0;
+        self.block = Block::None;
+        self.rep = None;
+        self.verify = false;
+        self.end = false;
+        self.trailer = None;
+
+        if self.next_byte()? != Some(BLOCK_RESET) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("No block starts at offset {}.", point.output),
+            ));
+        }
+        self.read_block_header(BLOCK_RESET)
+    }
+}
+
+/// Decodes the tokens of a stream read from the inner reader, without producing any output. Since
+/// nothing is decompressed, the trailer is read but the checksum is not verified.
+#[derive(Debug)]
+pub struct TokenReader<R> {
+    reader: Reader<R>,
+    /// Whether the tokens have ended, either after the last block or with an error.
+    done: bool,
+}
+
+impl<R: Read> TokenReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            reader: Reader::new(inner),
+            done: false,
+        }
+    }
+}
+
+impl<R: Read> Iterator for TokenReader<R> {
+    type Item = Result<Token>;
+
+    /// Reads exactly one token, along with its control byte if it starts a new token group.
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.reader.next_token() {
+            Ok(Some(tok)) => Some(Ok(tok)),
+            Ok(None) => {
+                self.done = true;
+                if self.reader.end {
+                    None
+                } else {
+                    Some(Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "Stream ended before the end marker.",
+                    )))
+                }
+            }
+            Err(err) => {
+                self.done = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::write_header;
+    use crate::test_util::cycled;
+    use crate::{Config, Writer};
+    use std::io::Write;
+
+    fn config() -> Config {
+        Config::builder()
+            .dict_size(0x80)
+            .max_chain_length(usize::MAX)
+            .build()
+    }
+
+    /// Frames the tokens of a stream that decodes to `output` as a single block, with the header
+    /// for `config()` and the trailer. The tokens have to end with the end marker themselves.
+    fn framed(tokens: &[u8], output: &[u8]) -> Vec<u8> {
+        let mut stream = vec![];
+        write_header(&mut stream, config().dict_size, config().checksum);
+        stream.push(BLOCK_TOKENS);
+        stream.extend_from_slice(tokens);
+        stream.push(BLOCK_END);
+
+        let mut checksum = Hasher::new(config().checksum);
+        checksum.update(output);
+        stream.extend_from_slice(&checksum.finish().unwrap().to_be_bytes());
+        stream
+    }
+
+    fn roundtrip(input: &[u8]) -> Result<Vec<u8>> {
+        let mut comp = Writer::new(Vec::new(), config());
+        comp.write_all(input)?;
+        let compressed = comp.finish()?;
+
+        let mut out = vec![];
+        Reader::new(&compressed[..]).read_to_end(&mut out)?;
+        Ok(out)
+    }
+
+    #[test]
+    fn test_roundtrip() -> Result<()> {
+        let inputs: [&[u8]; 6] = [
+            b"",
+            b"banana banana",
+            b"Hey, banana-ass! To banana or not to banana?",
+            b"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaab",
+            b"abcabcabcabcabcabcabcabcabcabcabcabcabcabcabcabcabcabcabX",
+            b"0123456789abcdefghijklmnopqrstuvwxyz",
+        ];
+
+        for input in inputs {
+            assert_eq!(input, &roundtrip(input)?[..]);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_overlapping_rep() -> Result<()> {
+        // "ab" followed by a rep that copies over its own output.
+        let compressed = framed(&[0b1100, b'a', b'b', 1, 6, 0, 0], b"abababab");
+
+        let mut out = vec![];
+        Reader::new(&compressed[..]).read_to_end(&mut out)?;
+        assert_eq!(b"abababab"[..], out[..]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_distance() {
+        // The rep reaches back 2 bytes, but only "ab" has been written.
+        let compressed = framed(&[0b1100, b'a', b'b', 2, 6, 0, 0], b"abababab");
+
+        let mut out = vec![];
+        let err = Reader::new(&compressed[..])
+            .read_to_end(&mut out)
+            .unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, err.kind());
+    }
+
+    #[test]
+    fn test_rep_history() -> Result<()> {
+        let read = |compressed: &[u8]| {
+            let mut out = vec![];
+            Reader::new(compressed).read_to_end(&mut out).map(|_| out)
+        };
+
+        // A run at distance 0 copies the one byte of history over and over.
+        let run = [b'a'; 10];
+        assert_eq!(run[..], read(&framed(&[0b110, b'a', 0, 9, 0, 0], &run))?[..]);
+
+        // The rep reaches back 2 bytes, one more than there is.
+        let err = read(&framed(&[0b110, b'a', 1, 2, 0, 0], b"aaa")).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, err.kind());
+
+        // Once the window is full, it holds the last `dict_size` bytes and no more.
+        let literals: Vec<u8> = (0..0x80).map(|i| i as u8).collect();
+        for (distance, valid) in [(0x7f, true), (0x80, false)] {
+            let mut tokens = vec![];
+            for group in literals.chunks(8) {
+                tokens.push(0);
+                tokens.extend_from_slice(group);
+            }
+            tokens.extend_from_slice(&[0b11, distance, 1, 0, 0]);
+            let output = [&literals[..], &[0]].concat();
+            assert_eq!(valid, read(&framed(&tokens, &output)).is_ok());
+        }
+
+        // Nothing can be reached across a reset block.
+        let mut compressed = framed(&[0b100, b'a', b'b', 0, 0], b"ab");
+        let trailer = compressed.split_off(compressed.len() - 5);
+        compressed.extend_from_slice(&[BLOCK_RESET, BLOCK_TOKENS, 0b11, 0, 2, 0, 0]);
+        compressed.extend_from_slice(&trailer);
+        let err = read(&compressed).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, err.kind());
+        Ok(())
+    }
+
+    #[test]
+    fn test_small_reads() -> Result<()> {
+        let compressed = framed(&[0b1100, b'a', b'b', 1, 6, 0, 0], b"abababab");
+        let mut reader = Reader::new(&compressed[..]);
+
+        let mut out = vec![];
+        let mut buf = [0; 3];
+        loop {
+            match reader.read(&mut buf)? {
+                0 => break,
+                n => out.extend_from_slice(&buf[..n]),
+            }
+        }
+        assert_eq!(b"abababab"[..], out[..]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_skip() -> Result<()> {
+        let input = cycled(b"Hey, banana-ass! To banana or not to banana? ", 0x1000);
+        let mut comp = Writer::new(Vec::new(), config());
+        comp.write_all(&input)?;
+        let compressed = comp.finish()?;
+
+        // The skips end in the middle of reps, and the last runs past the end of the stream.
+        for skip in [0, 1, 17, 0x3ff, 0x401, 0xfff, 0x1000, 0x2000] {
+            let mut reader = Reader::new(&compressed[..]);
+            assert_eq!(skip.min(input.len()), reader.skip(skip)?);
+
+            let mut out = vec![];
+            reader.read_to_end(&mut out)?;
+            assert_eq!(input[skip.min(input.len())..], out[..]);
+        }
+
+        // The checksum covers the skipped bytes too.
+        let mut flipped = compressed.clone();
+        flipped[10] ^= 0x20;
+        let mut reader = Reader::new(&flipped[..]);
+        assert_eq!(0x800, reader.skip(0x800)?);
+        assert_eq!(io::ErrorKind::InvalidData, reader.read_to_end(&mut vec![]).unwrap_err().kind());
+        Ok(())
+    }
+
+    #[test]
+    fn test_header() -> Result<()> {
+        // The window comes from the header, whatever the config was.
+        let input: Vec<u8> = (0..0x800).map(|i| (i % 251) as u8).collect();
+        let mut comp = Writer::new(Vec::new(), Config::builder().dict_size(0x400).build());
+        comp.write_all(&input)?;
+        let compressed = comp.finish()?;
+        assert_eq!(b"LZRS\x01\x01\x80\x08"[..], compressed[..8]);
+
+        let mut reader = Reader::new(&compressed[..]);
+        let mut out = vec![];
+        reader.read_to_end(&mut out)?;
+        assert_eq!(0x400, reader.dict_size);
+        assert_eq!(input, out);
+
+        // An empty stream still has a header.
+        assert_eq!(b""[..], roundtrip(b"")?[..]);
+        assert_eq!(0, Reader::new(&framed(&[0b1, 0, 0], b"")[..]).read(&mut [0; 4])?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_bad_header() {
+        let err = |stream: &[u8]| {
+            Reader::new(stream)
+                .read_to_end(&mut vec![])
+                .unwrap_err()
+                .kind()
+        };
+
+        assert_eq!(io::ErrorKind::UnexpectedEof, err(b""));
+        assert_eq!(io::ErrorKind::UnexpectedEof, err(b"LZR"));
+        assert_eq!(io::ErrorKind::UnexpectedEof, err(b"LZRS\x01"));
+        assert_eq!(io::ErrorKind::UnexpectedEof, err(b"LZRS\x01\x01"));
+        assert_eq!(io::ErrorKind::UnexpectedEof, err(b"LZRS\x01\x01\x80"));
+        assert_eq!(io::ErrorKind::InvalidData, err(b"LZRX\x01\x01\x80\x01"));
+        assert_eq!(io::ErrorKind::InvalidData, err(b"LZRS\x02\x01\x80\x01"));
+        assert_eq!(io::ErrorKind::InvalidData, err(b"LZRS\x01\x03\x80\x01"));
+        assert_eq!(io::ErrorKind::InvalidData, err(b"LZRS\x01\x01\x00"));
+        assert_eq!(io::ErrorKind::InvalidData, err(b"LZRS\x01\x01\x80\x80\x80\x80\x80\x01"));
+        assert_eq!(io::ErrorKind::InvalidData, err(&[&b"LZRS\x01\x01"[..], &[0xff; 16]].concat()));
+    }
+
+    #[test]
+    fn test_checksum() -> Result<()> {
+        let input = b"Hey, banana-ass! To banana or not to banana?";
+        let mut comp = Writer::new(Vec::new(), config());
+        comp.write_all(input)?;
+        let compressed = comp.finish()?;
+
+        let err = |stream: &[u8]| Reader::new(stream).read_to_end(&mut vec![]).unwrap_err();
+
+        // The first literal, the "H", decodes to a different byte.
+        let mut flipped = compressed.clone();
+        flipped[10] ^= 0x20;
+        assert_eq!(io::ErrorKind::InvalidData, err(&flipped).kind());
+
+        // The trailer itself is wrong.
+        let mut flipped = compressed.clone();
+        *flipped.last_mut().unwrap() ^= 1;
+        assert_eq!(io::ErrorKind::InvalidData, err(&flipped).kind());
+
+        // The stream is cut off after a token, right before the end marker.
+        let tokens = framed(&[0b0, b'H'], b"H");
+        assert_eq!(io::ErrorKind::UnexpectedEof, err(&tokens[..11]).kind());
+        Ok(())
+    }
+
+    #[test]
+    fn test_token_reader() -> Result<()> {
+        let compressed = framed(&[0b1100, b'a', b'b', 1, 6, 0, 0], b"abababab");
+        let tokens = TokenReader::new(&compressed[..]).collect::<Result<Vec<_>>>()?;
+        assert_eq!(
+            vec![
+                Token::Literal { byte: b'a' },
+                Token::Literal { byte: b'b' },
+                Token::Rep { distance: 1, length: 6 },
+            ],
+            tokens
+        );
+
+        // Without the end marker, the last item is the error.
+        let mut tokens = TokenReader::new(&compressed[..12]);
+        assert_eq!(Token::Literal { byte: b'a' }, tokens.next().unwrap()?);
+        assert_eq!(Token::Literal { byte: b'b' }, tokens.next().unwrap()?);
+        assert_eq!(io::ErrorKind::UnexpectedEof, tokens.next().unwrap().unwrap_err().kind());
+        assert!(tokens.next().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_seek_block() -> Result<()> {
+        let input: Vec<u8> = (0..0x380)
+            .map(|i| b"banana, bandana"[i % 15] ^ (i / 0x100) as u8)
+            .collect();
+        let mut comp = Writer::new(Vec::new(), Config { block_size: Some(0x100), ..config() });
+        comp.write_all(&input)?;
+        comp.flush()?;
+        let points = comp.seek_points().to_vec();
+        let compressed = comp.finish()?;
+        assert_eq!(3, points.len());
+
+        // Each block decodes on its own, and together they are the whole input.
+        let mut out = vec![0; 0x100];
+        Reader::new(io::Cursor::new(&compressed)).read_exact(&mut out)?;
+        for &point in &points {
+            let mut reader = Reader::new(io::Cursor::new(&compressed));
+            reader.seek_block(point)?;
+            let mut block = vec![];
+            reader.take(0x100).read_to_end(&mut block)?;
+            assert_eq!(input[point.input as usize..][..block.len()], block[..]);
+            out.extend_from_slice(&block);
+        }
+        assert_eq!(input, out);
+
+        // A reader can seek back and forth, and read on to the end.
+        let mut reader = Reader::new(io::Cursor::new(&compressed));
+        reader.seek_block(points[2])?;
+        reader.seek_block(points[1])?;
+        let mut out = vec![];
+        reader.read_to_end(&mut out)?;
+        assert_eq!(input[0x200..], out[..]);
+
+        let mut point = points[1];
+        point.output += 1;
+        let err = Reader::new(io::Cursor::new(&compressed)).seek_block(point).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+        Ok(())
+    }
+
+    #[test]
+    fn test_checksum_kinds() -> Result<()> {
+        let input = b"Hey, banana-ass! To banana or not to banana?";
+
+        for checksum in [Checksum::None, Checksum::Adler32, Checksum::Crc32] {
+            let config = Config::builder().dict_size(0x80).checksum(checksum).build();
+            let mut comp = Writer::new(Vec::new(), config);
+            comp.write_all(input)?;
+            let compressed = comp.finish()?;
+            assert_eq!(checksum as u8, compressed[5]);
+
+            let mut reader = Reader::new(&compressed[..]);
+            let mut out = vec![];
+            reader.read_to_end(&mut out)?;
+            assert_eq!(input[..], out[..]);
+            assert_eq!(checksum, reader.checksum.kind());
+
+            // Without a checksum, a corrupt literal goes unnoticed.
+            let mut flipped = compressed.clone();
+            flipped[10] ^= 0x20;
+            let res = Reader::new(&flipped[..]).read_to_end(&mut vec![]);
+            assert_eq!(checksum == Checksum::None, res.is_ok());
+        }
+        Ok(())
+    }
+}
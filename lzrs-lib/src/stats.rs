@@ -0,0 +1,43 @@
+//! Statistics collected while compressing, for tuning a [`Config`](crate::Config).
+
+/// Counts of what a [`Writer`](crate::Writer) has done so far in the current stream.
+#[derive(Copy, Clone, Default, Eq, PartialEq, Debug)]
+pub struct Stats {
+    /// The number of uncompressed bytes written.
+    pub input_bytes: u64,
+    /// The number of compressed bytes encoded, including the header and trailer. Some of these may
+    /// still be buffered rather than written to the inner writer.
+    pub output_bytes: u64,
+    /// The number of [`Token::Literal`](crate::Token::Literal)s encoded.
+    pub literal_count: u64,
+    /// The number of [`Token::Rep`](crate::Token::Rep)s encoded, not counting the end marker.
+    pub rep_count: u64,
+    /// The sum of the lengths of every rep.
+    pub total_match_length: u64,
+    /// The number of blocks that were stored as they are, since encoding their tokens would have
+    /// taken more space. Their tokens are still counted above.
+    pub stored_blocks: u64,
+    /// The number of times the match finder was searched for a match.
+    pub searches: u64,
+    /// The number of candidate positions visited by those searches, following the hash chains.
+    pub chain_hops: u64,
+}
+
+impl Stats {
+    /// The compression ratio, as the number of input bytes per output byte. Higher is better.
+    pub fn ratio(&self) -> f64 {
+        if self.output_bytes == 0 {
+            return 0.0;
+        }
+        self.input_bytes as f64 / self.output_bytes as f64
+    }
+
+    /// The average number of candidate positions visited per search. A high average for a low
+    /// ratio means that many positions share a hash without matching.
+    pub fn average_chain_length(&self) -> f64 {
+        if self.searches == 0 {
+            return 0.0;
+        }
+        self.chain_hops as f64 / self.searches as f64
+    }
+}
@@ -0,0 +1,54 @@
+//! Helpers for compressing and decompressing whole streams at once.
+
+use std::fmt::Debug;
+use std::io::{self, Read, Write};
+
+use crate::{Config, Reader, Writer};
+
+/// Compresses everything read from `reader` into `writer` and finishes the stream, returning the
+/// number of bytes read. An invalid `config` is returned as an [`io::ErrorKind::InvalidInput`]
+/// error.
+pub fn compress<R: Read, W: Write + Debug>(
+    mut reader: R,
+    writer: W,
+    config: Config,
+) -> io::Result<u64> {
+    let mut comp = Writer::try_new(writer, config)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+
+    let read = io::copy(&mut reader, &mut comp)?;
+    comp.finish()?;
+    Ok(read)
+}
+
+/// Decompresses everything read from `reader` into `writer`, returning the number of bytes
+/// written.
+///
+/// A corrupt stream is returned as an [`io::ErrorKind::InvalidData`] error, and one that ends in
+/// the middle of a token as an [`io::ErrorKind::UnexpectedEof`] error.
+pub fn decompress<R: Read, W: Write>(reader: R, mut writer: W) -> io::Result<u64> {
+    io::copy(&mut Reader::new(reader), &mut writer)
+}
+
+/// Compresses `data` into a new `Vec` as a finished stream. Panics if the config is invalid, like
+/// [`Writer::new`].
+///
+/// ```
+/// use lzrs_lib::{compress_to_vec, decompress_to_vec, Config};
+///
+/// let compressed = compress_to_vec(b"hello", Config::default());
+/// assert_eq!(b"hello"[..], decompress_to_vec(&compressed).unwrap()[..]);
+/// ```
+pub fn compress_to_vec(data: &[u8], config: Config) -> Vec<u8> {
+    let mut comp = Writer::new(Vec::with_capacity(data.len() / 2), config);
+    // Writing to a `Vec` can't fail.
+    comp.write_all(data).unwrap();
+    comp.finish().unwrap()
+}
+
+/// Decompresses the whole stream in `data` into a new `Vec`. Fails like [`decompress`].
+pub fn decompress_to_vec(data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(data.len() * 2);
+    Reader::new(data).read_to_end(&mut out)?;
+    Ok(out)
+}
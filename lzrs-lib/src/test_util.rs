@@ -0,0 +1,30 @@
+//! Inputs shared by the unit tests.
+
+/// The seed the tests use for [`random_u64s`] and [`random_bytes`] unless they need another one.
+pub(crate) const SEED: u64 = 0x2545_f491_4f6c_dd1d;
+
+/// Returns `len` values from a xorshift seeded with `seed`, which must not be 0. The values are
+/// the same on every run, so failures can be reproduced.
+pub(crate) fn random_u64s(len: usize, seed: u64) -> Vec<u64> {
+    assert_ne!(0, seed, "A xorshift can't be seeded with 0.");
+    let mut state = seed;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        })
+        .collect()
+}
+
+/// Returns the low byte of each of `len` values from [`random_u64s`], so that there is little to
+/// match.
+pub(crate) fn random_bytes(len: usize, seed: u64) -> Vec<u8> {
+    random_u64s(len, seed).into_iter().map(|v| v as u8).collect()
+}
+
+/// Repeats `text` until it is `len` bytes long, so that there is plenty to match.
+pub(crate) fn cycled(text: &[u8], len: usize) -> Vec<u8> {
+    text.iter().copied().cycle().take(len).collect()
+}
@@ -0,0 +1,44 @@
+//! The instrumentation that the debug UI follows the writer with. Without the `trace` feature,
+//! it compiles to nothing and `tracing` isn't a dependency.
+
+#[cfg(feature = "trace")]
+macro_rules! trace {
+    ($($arg:tt)*) => { tracing::trace!($($arg)*) };
+}
+
+#[cfg(feature = "trace")]
+macro_rules! trace_span {
+    ($($arg:tt)*) => { tracing::trace_span!($($arg)*) };
+}
+
+#[cfg(not(feature = "trace"))]
+macro_rules! trace {
+    ($($arg:tt)*) => {};
+}
+
+#[cfg(not(feature = "trace"))]
+macro_rules! trace_span {
+    ($($arg:tt)*) => {
+        $crate::trace::Span
+    };
+}
+
+pub(crate) use {trace, trace_span};
+
+#[cfg(feature = "trace")]
+pub(crate) use tracing::Span;
+
+/// Stands in for a `tracing::Span`.
+#[cfg(not(feature = "trace"))]
+pub(crate) struct Span;
+
+/// Stands in for the guard of an entered `tracing::Span`.
+#[cfg(not(feature = "trace"))]
+pub(crate) struct Entered;
+
+#[cfg(not(feature = "trace"))]
+impl Span {
+    pub(crate) fn enter(&self) -> Entered {
+        Entered
+    }
+}
@@ -0,0 +1,110 @@
+//! Small adapters that are useful alongside the [`Writer`](crate::Writer).
+
+use std::io::{IoSlice, Result, Write};
+
+/// Forwards everything written to it to the inner writer, counting the bytes it accepts. As the
+/// inner writer of a [`Writer`](crate::Writer), it counts the compressed bytes that have actually
+/// been written out.
+///
+/// ```
+/// use std::io::Write;
+/// use lzrs_lib::{util::CountingWriter, Config, Writer};
+///
+/// let input = b"To banana or not to banana? To banana or not to banana?";
+/// let mut comp = Writer::new(CountingWriter::new(vec![]), Config::default());
+/// comp.write_all(input).unwrap();
+/// let counted = comp.finish().unwrap();
+///
+/// assert_eq!(counted.get_ref().len() as u64, counted.count());
+/// println!("Ratio: {:.2}", input.len() as f64 / counted.count() as f64);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CountingWriter<W> {
+    inner: W,
+    count: u64,
+}
+
+impl<W> CountingWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self { inner, count: 0 }
+    }
+
+    /// The number of bytes the inner writer has accepted so far.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// The inner writer. Bytes written to it directly aren't counted.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.count += written as u64;
+        Ok(written)
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> Result<usize> {
+        let written = self.inner.write_vectored(bufs)?;
+        self.count += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::cycled;
+    use crate::{Config, Writer};
+
+    #[test]
+    fn test_count() -> Result<()> {
+        let mut counted = CountingWriter::new(vec![]);
+        assert_eq!(0, counted.count());
+        counted.write_all(b"banana")?;
+        assert_eq!(4, counted.write_vectored(&[IoSlice::new(b"-"), IoSlice::new(b"ass")])?);
+        assert_eq!(10, counted.count());
+        assert_eq!(b"banana-ass"[..], counted.get_ref()[..]);
+
+        // Only the bytes a short write accepts are counted.
+        let mut buf = [0; 4];
+        let mut counted = CountingWriter::new(&mut buf[..]);
+        assert_eq!(4, counted.write(b"banana")?);
+        assert_eq!(0, counted.write(b"banana")?);
+        assert_eq!(4, counted.count());
+        Ok(())
+    }
+
+    #[test]
+    fn test_count_compressed() -> Result<()> {
+        let input = cycled(b"To banana or not to banana? ", 0x1000);
+
+        let config = Config { high_water_mark: Some(0x10), ..Config::default() };
+        let mut comp = Writer::new(CountingWriter::new(vec![]), config);
+        comp.write_all(&input)?;
+        assert_eq!(comp.get_ref().get_ref().len() as u64, comp.get_ref().count());
+
+        // Everything encoded so far is written out by a flush.
+        comp.flush()?;
+        assert_eq!(comp.stats().output_bytes, comp.get_ref().count());
+
+        let counted = comp.finish()?;
+        assert_eq!(counted.get_ref().len() as u64, counted.count());
+        Ok(())
+    }
+}
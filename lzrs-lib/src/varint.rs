@@ -0,0 +1,108 @@
+//! LEB128 variable-length integers, used for the header and for the `distance` and `length` of
+//! a [`Token::Rep`](crate::Token::Rep).
+//!
+//! Each byte holds 7 bits of the value, least significant first, and has its high bit set if
+//! more bytes follow. Values up to 127 take a single byte.
+
+/// The most bytes a `u64` can take.
+pub(crate) const MAX_LEN: usize = 10;
+
+/// Appends `v` to the buffer as an LEB128 varint.
+pub(crate) fn write_varint(buf: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        buf.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    buf.push(v as u8);
+}
+
+/// The number of bytes `v` takes as a varint.
+pub(crate) fn varint_len(v: u64) -> usize {
+    let bits = 64 - v.leading_zeros() as usize;
+    bits.max(1).div_ceil(7)
+}
+
+/// Reads a varint from the buffer at `pos`, advancing `pos` past it. Returns `None` and leaves
+/// `pos` alone if the buffer ends before the varint does, or if it doesn't fit in a `u64`.
+pub(crate) fn read_varint(buf: &[u8], pos: &mut usize) -> Option<u64> {
+    let mut v = 0;
+
+    for (i, &byte) in buf.get(*pos..)?.iter().take(MAX_LEN).enumerate() {
+        // Only the lowest bit of the last byte is left.
+        if i == MAX_LEN - 1 && byte > 1 {
+            return None;
+        }
+        v |= ((byte & 0x7f) as u64) << (i * 7);
+
+        if byte & 0x80 == 0 {
+            *pos += i + 1;
+            return Some(v);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{random_u64s, SEED};
+
+    fn encoded(v: u64) -> Vec<u8> {
+        let mut buf = vec![];
+        write_varint(&mut buf, v);
+        buf
+    }
+
+    #[test]
+    fn test_boundaries() {
+        assert_eq!(vec![0], encoded(0));
+        assert_eq!(vec![0x7f], encoded(127));
+        assert_eq!(vec![0x80, 0x01], encoded(128));
+        assert_eq!(vec![0xff, 0x7f], encoded(16383));
+        assert_eq!(vec![0x80, 0x80, 0x01], encoded(16384));
+        assert_eq!(MAX_LEN, encoded(u64::MAX).len());
+
+        for v in [0, 127, 128, 16383, 16384, u64::MAX] {
+            let buf = encoded(v);
+            assert_eq!(buf.len(), varint_len(v));
+            let mut pos = 0;
+            assert_eq!(Some(v), read_varint(&buf, &mut pos));
+            assert_eq!(buf.len(), pos);
+        }
+    }
+
+    #[test]
+    fn test_incomplete() {
+        let mut pos = 0;
+        assert_eq!(None, read_varint(&[], &mut pos));
+        assert_eq!(None, read_varint(&[0x80, 0x80], &mut pos));
+        assert_eq!(None, read_varint(&[0xff; 16], &mut pos));
+        assert_eq!(None, read_varint(&[&[0xff; 9][..], &[0x02]].concat(), &mut pos));
+        assert_eq!(0, pos);
+
+        let mut pos = 3;
+        assert_eq!(None, read_varint(&[0], &mut pos));
+        assert_eq!(3, pos);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        // Random values shifted by every amount, so that they cover every length.
+        let values: Vec<u64> = random_u64s(1000, SEED)
+            .into_iter()
+            .enumerate()
+            .map(|(i, v)| v >> (i % 64))
+            .collect();
+
+        let mut buf = vec![];
+        for &v in &values {
+            write_varint(&mut buf, v);
+        }
+
+        let mut pos = 0;
+        for &v in &values {
+            assert_eq!(Some(v), read_varint(&buf, &mut pos));
+        }
+        assert_eq!(buf.len(), pos);
+    }
+}
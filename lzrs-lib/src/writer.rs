@@ -1,162 +1,2029 @@
-use std::io::{Write, Result};
-use tracing::{debug, trace_span, field, trace};
-use std::fmt::Debug;
+//! The compressing [`Writer`].
+//!
+//! Between the header and the trailer (see `frame.rs`), the output is a sequence of blocks. Each
+//! block of tokens is a sequence of token groups, and each group starts with a control byte
+//! followed by up to 8 tokens. Bit `i` (least significant
+//! first) of the control byte describes the `i`th token of the group:
+//!
+//! - `0`: a [`Token::Literal`], stored as the byte itself.
+//! - `1`: a [`Token::Rep`], stored as its `distance` followed by its `length`, both as LEB128
+//!   varints. A `length` of 0 ends the block.
 
-use crate::{Config, Token};
+use std::io::{Error, ErrorKind, Write, Result};
+use std::fmt::{self, Debug};
 
+use crate::checksum::Hasher;
+use crate::frame::{
+    write_header, BLOCK_END, BLOCK_RESET, BLOCK_STORED, BLOCK_SYNC, BLOCK_TOKENS, END,
+};
+use crate::match_finder::{reach, HashChain, MatchFinder, HASH_LEN};
+use crate::trace::{trace, trace_span, Span};
+use crate::varint::{varint_len, write_varint};
+use crate::{Buffer, Config, ConfigError, RingBuf, Stats, Token};
+
+/// The size the window starts at when it grows as needed.
+const INITIAL_WINDOW: usize = 0x1000;
+
+/// The number of input bytes after which a block is ended. Blocks end at the first token boundary
+/// past this, or when the writer is flushed.
+const BLOCK_SIZE: usize = 0x8000;
+
+/// Where a block that can be decoded on its own starts, when the config has a `block_size`. See
+/// [`Reader::seek_block`](crate::Reader::seek_block).
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct SeekPoint {
+    /// The number of input bytes before the block.
+    pub input: u64,
+    /// The offset of the block in the output, counting from the start of the header.
+    pub output: u64,
+}
+
+/// Compresses everything written to it into the inner writer.
+///
+/// Written bytes are buffered in a lookahead and only tokenized once enough of them are available
+/// to find the longest match, so matches can span any number of `write` calls. Whatever is left
+/// in the lookahead is tokenized by `flush` and [`Writer::finish`].
+///
+/// The window of previous bytes that matches are found in is kept in a `B`, which is a [`RingBuf`]
+/// unless the writer is created with [`Writer::with_window`]. The matches are found by an `F`,
+/// which is a [`HashChain`] unless chosen the same way.
 #[derive(Debug)]
-pub struct Writer<W> {
+pub struct Writer<W, B = RingBuf, F = HashChain> {
     dict_size: usize,
+    min_match: usize,
+    max_match: usize,
+    lazy: bool,
+    optimal: bool,
+    store_only: bool,
+    strict_window: bool,
+    high_water_mark: Option<usize>,
+    block_size: Option<usize>,
 
     inner: W,
     write_buf: Vec<u8>,
 
-    dict: Vec<u8>,
-    head: usize,
+    /// The bytes that have been written but not yet tokenized.
+    lookahead: Vec<u8>,
+
+    /// The index in `write_buf` of the start of the current block of tokens, if there is one.
+    block_start: Option<usize>,
+    /// The input consumed by the tokens of the current block, in case it ends up stored.
+    block_input: Vec<u8>,
+
+    /// The index in `write_buf` of the control byte of the current token group.
+    ctrl: usize,
+    /// The bit in the control byte to be used by the next token. A new group is started when this
+    /// is 0.
+    ctrl_bit: u8,
+
+    dict: B,
+    /// The number of bytes `dict` has room for, and the most it can grow to. These are the same
+    /// unless the window grows.
+    window_size: usize,
+    max_window: usize,
+    /// The furthest back a match can be, which is less than `dict_size` with a `max_distance`.
+    reach: usize,
+    /// The total number of bytes written into the dictionary since it was last cleared, which is
+    /// also the virtual index of the next one in `dict`.
+    pos: usize,
+    finder: F,
 
-    map: [u32; 0x100],
-    chain: Vec<u32>,
+    /// The input consumed by tokens since the window last started over, which it does again once
+    /// this reaches the `block_size`.
+    reset_input: usize,
+    seek_points: Vec<SeekPoint>,
+
+    /// The checksum of everything written, for the trailer.
+    checksum: Hasher,
+
+    stats: Stats,
+
+    /// The error that failed the last `fmt::Write` call, which can only return `fmt::Error`.
+    fmt_error: Option<Error>,
+    /// The error that failed compressing after a `write` had already taken its bytes, which is
+    /// returned by the next call instead.
+    write_error: Option<Error>,
 }
 
-impl<W: Write + Debug> Write for Writer<W> {
+impl<W: Write + Debug, B: Buffer + Write, F: MatchFinder> Write for Writer<W, B, F> {
     fn write(&mut self, buf: &[u8]) -> Result<usize> {
-        let span = trace_span!("write", "dict.ptr" = field::Empty, "dict.head" = field::Empty, "dict.len" = field::Empty);
-        span.record("dict.ptr", &(self.dict.as_ptr() as u64));
-        span.record("dict.head", &(self.head as u32));
-        span.record("dict.len", &(self.dict.len() as u32));
-
+        let span = trace_span!(
+            "write",
+            "dict.window" = tracing::field::Empty,
+            "dict.head" = tracing::field::Empty
+        );
+        self.record_dict(&span);
         let _enter = span.enter();
 
         trace!("Writing");
-        let (consumed, tok) = self.next_token(buf);
-        trace!(consumed = consumed, "Produced a token.");
+        if let Some(err) = self.write_error.take() {
+            return Err(err);
+        }
+        self.check_window(buf.len())?;
+        self.checksum.update(buf);
+        self.stats.input_bytes += buf.len() as u64;
+        self.lookahead.extend_from_slice(buf);
 
-        self.write_to_dictionary(&buf[..consumed]);
-        self.write_token(&tok)?;
-        Ok(consumed)
+        // The bytes are taken either way, so a failure is left for the next call to return, or
+        // they would be written again by a retry.
+        if let Err(err) = self.compress(self.max_match + HASH_LEN) {
+            self.write_error = Some(err);
+        }
+        Ok(buf.len())
     }
 
+    /// Tokenizes the whole lookahead and ends the current block, so that everything written so far
+    /// can be decompressed from the inner writer, then flushes it. Matches can still reach back
+    /// across the flush.
     fn flush(&mut self) -> Result<()> {
+        self.compress(1)?;
+        self.end_block()?;
         self.inner.write_all(&self.write_buf)?;
-        Ok(())
+        self.write_buf.clear();
+        self.inner.flush()
+    }
+}
+
+/// Formatted text is written as its UTF-8 bytes, like [`Writer::compress_str`]. An error leaves
+/// the `io::Error` behind it for [`Writer::take_fmt_error`].
+impl<W: Write + Debug, B: Buffer + Write, F: MatchFinder> fmt::Write for Writer<W, B, F> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.compress_str(s).map_err(|err| {
+            self.fmt_error = Some(err);
+            fmt::Error
+        })
     }
 }
 
 impl<W: Write + Debug> Writer<W> {
+    /// Creates a writer, panicking if the config is invalid. See [`Writer::try_new`].
     pub fn new(inner: W, config: Config) -> Self {
-        if config.dict_size > std::u32::MAX.try_into().unwrap() {
-            panic!("Dictionary must be less than or equal to {} bytes!", std::u32::MAX);
+        Self::with_window(inner, config)
+    }
+
+    /// Creates a writer, or returns why the config can't be used.
+    pub fn try_new(inner: W, config: Config) -> std::result::Result<Self, ConfigError> {
+        Self::try_with_window(inner, config)
+    }
+}
+
+impl<'a, W: Write + Debug> Writer<&'a mut W> {
+    /// Creates a writer into a borrowed inner writer, which can be used again once the stream is
+    /// finished, panicking if the config is invalid.
+    pub fn by_ref(inner: &'a mut W, config: Config) -> Self {
+        Self::new(inner, config)
+    }
+}
+
+impl<W: Write + Debug, B: Buffer + Write, F: MatchFinder> Writer<W, B, F> {
+    /// Creates a writer that keeps its window in a `B` and finds matches with an `F`, panicking if
+    /// the config is invalid. See [`Writer::try_with_window`].
+    pub fn with_window(inner: W, config: Config) -> Self {
+        match Self::try_with_window(inner, config) {
+            Ok(writer) => writer,
+            Err(err) => panic!("{}", err),
         }
+    }
+
+    /// Creates a writer that keeps its window in a `B` and finds matches with an `F`, or returns
+    /// why the config can't be used.
+    pub fn try_with_window(inner: W, config: Config) -> std::result::Result<Self, ConfigError> {
+        config.validate()?;
 
-        Self {
+        let mut write_buf = vec![];
+        write_header(&mut write_buf, config.dict_size, config.checksum);
+        let stats = Stats {
+            output_bytes: write_buf.len() as u64,
+            ..Stats::default()
+        };
+
+        // Matches never reach further back than `max_window`, so that is all the window needs to
+        // grow to.
+        let (window_size, max_window) = if config.grow_window {
+            let max_window = reach(&config);
+            (INITIAL_WINDOW.min(max_window), max_window)
+        } else {
+            (config.dict_size, config.dict_size)
+        };
+
+        Ok(Self {
             inner,
-            dict: Vec::with_capacity(config.dict_size),
-            head: 0,
-            map: [std::u32::MAX; 0x100],
-            chain: Vec::with_capacity(config.dict_size),
-            write_buf: vec![],
+            dict: B::with_capacity(window_size),
+            window_size,
+            max_window,
+            reach: reach(&config),
+            pos: 0,
+            finder: F::with_config(&config),
+            reset_input: 0,
+            seek_points: vec![],
+            write_buf,
+            lookahead: vec![],
+            block_start: None,
+            block_input: vec![],
+            ctrl: 0,
+            ctrl_bit: 0,
             dict_size: config.dict_size,
-        }
+            min_match: config.min_match,
+            max_match: config.max_match,
+            lazy: config.lazy,
+            optimal: config.optimal,
+            store_only: config.store_only,
+            strict_window: config.strict_window,
+            high_water_mark: config.high_water_mark,
+            block_size: config.block_size,
+            checksum: Hasher::new(config.checksum),
+            stats,
+            fmt_error: None,
+            write_error: None,
+        })
     }
 
+    /// Ends the stream, writing everything left to the inner writer, and returns it.
     pub fn finish(mut self) -> Result<W> {
-        self.flush()?;
+        self.finish_stream()?;
         Ok(self.inner)
     }
 
-    fn next_token(&self, lookahead: &[u8]) -> (usize, Token) {
-        let mut best_match = (0, None);
+    /// The inner writer. Output is only written to it by `flush` and [`Writer::finish`], or once
+    /// more than the `high_water_mark` of the config is buffered.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// The inner writer. Writing to it directly will corrupt the stream.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Returns the inner writer without flushing or ending the stream, unlike
+    /// [`Writer::finish`]. Whatever hasn't been written to it yet is lost.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// Finishes the current stream into the current inner writer, then starts a new stream into
+    /// `inner`, returning the old inner writer. The allocations are kept for the new stream.
+    pub fn reset(&mut self, inner: W) -> Result<W> {
+        self.finish_stream()?;
+
+        self.write_buf.clear();
+        write_header(&mut self.write_buf, self.dict_size, self.checksum.kind());
+        self.lookahead.clear();
+        self.block_start = None;
+        self.block_input.clear();
+        self.ctrl = 0;
+        self.ctrl_bit = 0;
+
+        self.clear_window();
+        self.seek_points.clear();
+        self.checksum.reset();
+        self.stats = Stats {
+            output_bytes: self.write_buf.len() as u64,
+            ..Stats::default()
+        };
+
+        Ok(std::mem::replace(&mut self.inner, inner))
+    }
+
+    /// Primes the window with a preset dictionary, so that the input can match against it from the
+    /// start without the dictionary itself being part of the stream. Only the last `dict_size`
+    /// bytes are kept. The stream can then only be decompressed by a
+    /// [`Reader`](crate::Reader) given the same dictionary.
+    ///
+    /// Panics if anything has already been written. [`Writer::reset`] clears the dictionary too.
+    pub fn set_dictionary(&mut self, dict: &[u8]) {
+        assert!(
+            self.pos == 0 && self.lookahead.is_empty(),
+            "The dictionary must be set before anything is written."
+        );
+
+        let dict = &dict[dict.len().saturating_sub(self.dict_size)..];
+        self.write_to_dictionary(dict, dict.len());
+    }
+
+    /// Writes the UTF-8 bytes of `s`, the same as `write_all` would. The writer knows nothing of
+    /// text, so the stream decompresses back to those bytes, not to a `str`.
+    pub fn compress_str(&mut self, s: &str) -> Result<()> {
+        self.write_all(s.as_bytes())
+    }
+
+    /// Takes the error that failed the last write through `fmt::Write`, if there was one.
+    pub fn take_fmt_error(&mut self) -> Option<Error> {
+        self.fmt_error.take()
+    }
+
+    /// Flushes like `flush`, and then marks the boundary with an empty sync block. A reader given
+    /// the output up to the marker decompresses everything written before it, without waiting on
+    /// anything after it, and skips the marker itself.
+    pub fn sync_flush(&mut self) -> Result<()> {
+        self.compress(1)?;
+        self.end_block()?;
+        self.write_buf.push(BLOCK_SYNC);
+        self.stats.output_bytes += 1;
+        self.flush()
+    }
+
+    /// Where each block after the first starts in the current stream, in order, if the config has
+    /// a `block_size`. The first block starts right after the header, where the stream does.
+    pub fn seek_points(&self) -> &[SeekPoint] {
+        &self.seek_points
+    }
+
+    /// The statistics of the current stream so far.
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    /// Returns the tokens for `input` without encoding them, as if it were written and flushed.
+    /// Anything written before is tokenized and encoded first. The dictionary is updated just
+    /// like it would be by writing, so any following input can match against this one. Only fails
+    /// like writing would, if the writer is in a bad state.
+    ///
+    /// The tokenized bytes are not part of the stream, so anything written after them can only be
+    /// decompressed by a reader that has them too, like a preset dictionary.
+    pub fn tokenize(&mut self, input: &[u8]) -> Result<Vec<Token>> {
+        self.check_window(input.len())?;
+        self.compress(1)?;
+        self.lookahead.extend_from_slice(input);
+
+        let mut tokens = vec![];
+        self.parse(1, |_, tok, _| {
+            tokens.push(tok);
+            Ok(true)
+        })?;
+
+        Ok(tokens)
+    }
+
+    /// Encodes the tokens as they are, without searching for matches, as if the bytes they decode
+    /// to were written. Anything written before is tokenized first. The decoded bytes are written
+    /// into the dictionary, so that later tokens and writes can match against them.
+    ///
+    /// Fails with [`ErrorKind::InvalidInput`] without encoding anything if a token is
+    /// the end marker or reaches further back than the window holds or the config allows, or if
+    /// the config has a `block_size`, since the tokens could reach across the start of a block.
+    pub fn write_tokens(&mut self, tokens: &[Token]) -> Result<()> {
+        if self.block_size.is_some() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Tokens can't be written with a block size.",
+            ));
+        }
+        self.compress(1)?;
+
+        // The tokens can match each other, so they are decoded past the end of the dictionary.
+        let mut decoded = vec![];
+        for tok in tokens {
+            match *tok {
+                Token::Literal { byte } => decoded.push(byte),
+                Token::Rep { length: 0, .. } => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        "The end marker can't be written as a token.",
+                    ));
+                }
+                Token::Rep { distance, length } => {
+                    // The window holds the last `dict.len()` bytes before the decoded ones, which
+                    // may be fewer than `dict_size` when it grows.
+                    let head = self.pos + decoded.len();
+                    if distance >= self.reach || distance >= self.dict.len() + decoded.len() {
+                        return Err(Error::new(
+                            ErrorKind::InvalidInput,
+                            format!("Distance {} is outside of the dictionary.", distance),
+                        ));
+                    }
+
+                    for i in head - distance - 1..head - distance - 1 + length {
+                        let byte = match i.checked_sub(self.pos) {
+                            Some(i) => decoded[i],
+                            None => self.dict[i],
+                        };
+                        decoded.push(byte);
+                    }
+                }
+            }
+        }
+
+        self.check_window(decoded.len())?;
+        self.checksum.update(&decoded);
+        self.stats.input_bytes += decoded.len() as u64;
+        self.write_to_dictionary(&decoded, decoded.len());
+
+        let mut start = 0;
+        for &tok in tokens {
+            let consumed = match tok {
+                Token::Literal { .. } => 1,
+                Token::Rep { length, .. } => length,
+            };
+            trace_token(consumed, &tok);
+            self.encode(tok, &decoded[start..start + consumed])?;
+            start += consumed;
+        }
+        Ok(())
+    }
+
+    /// Compresses as much of the start of `input` as fits in `max_out` bytes of output, writing
+    /// them to the inner writer, and returns how many bytes of input were consumed and how many
+    /// bytes of output were produced. Everything consumed can be decompressed from the output, as
+    /// after `flush`, and the next call carries on with `input[consumed..]`.
+    ///
+    /// Room is kept for the longest token and for ending the block, so the output may stop short
+    /// of `max_out`, and nothing is consumed if not even that fits. Tokens are chosen one at a
+    /// time, without optimal parsing. Anything written before, including the header, is written
+    /// to the inner writer first and doesn't count against `max_out`.
+    pub fn compress_bounded(&mut self, input: &[u8], max_out: usize) -> Result<(usize, usize)> {
+        self.check_window(input.len())?;
+        self.compress(1)?;
+        self.end_block()?;
+        self.inner.write_all(&self.write_buf)?;
+        self.write_buf.clear();
+
+        // The block kind and control byte of the token, the token, then the end marker with its
+        // own control byte, and the reset block that may follow it.
+        let reserve = 2 + varint_len(self.dict_size as u64) + varint_len(self.max_match as u64) + 4;
+        if reserve > max_out {
+            return Ok((0, 0));
+        }
+
+        let start = self.stats.output_bytes;
+        let limit = (max_out - reserve) as u64;
+        let optimal = std::mem::replace(&mut self.optimal, false);
+        self.lookahead.extend_from_slice(input);
+        let res = self.parse(1, |comp, tok, input| {
+            comp.encode(tok, input)?;
+            Ok(comp.stats.output_bytes - start <= limit)
+        });
+        self.optimal = optimal;
+
+        // Whatever wasn't tokenized is left to the caller.
+        let consumed = input.len() - self.lookahead.len();
+        self.lookahead.clear();
+        self.checksum.update(&input[..consumed]);
+        self.stats.input_bytes += consumed as u64;
+        res?;
+
+        self.end_block()?;
+        self.inner.write_all(&self.write_buf)?;
+        self.write_buf.clear();
+        Ok((consumed, (self.stats.output_bytes - start) as usize))
+    }
+
+    /// Tokenizes the rest of the lookahead and ends the stream with the last block and the
+    /// trailer, writing everything not yet flushed to the inner writer.
+    fn finish_stream(&mut self) -> Result<()> {
+        self.compress(1)?;
+        self.end_block()?;
+        self.write_buf.push(BLOCK_END);
+        self.stats.output_bytes += 1;
+        if let Some(checksum) = self.checksum.finish() {
+            self.write_buf.extend_from_slice(&checksum.to_be_bytes());
+            self.stats.output_bytes += 4;
+        }
+        self.inner.write_all(&self.write_buf)
+    }
+
+    /// Tokenizes and encodes the lookahead until fewer than `min_lookahead` bytes are left in it,
+    /// ending blocks as they fill up. Wherever the window starts over, the block is ended and the
+    /// start of the next one is marked. Fails first with any error left by the last `write`.
+    fn compress(&mut self, min_lookahead: usize) -> Result<()> {
+        if let Some(err) = self.write_error.take() {
+            return Err(err);
+        }
+        self.parse(min_lookahead, |comp, tok, input| comp.encode(tok, input).map(|()| true))
+    }
+
+    /// Ends the current block and starts the next one with a reset block, recording where it is.
+    fn mark_reset(&mut self) -> Result<()> {
+        self.end_block()?;
+        let input = self.seek_points.last().map_or(0, |point| point.input);
+        self.seek_points.push(SeekPoint {
+            input: input + self.reset_input as u64,
+            output: self.stats.output_bytes,
+        });
+        self.write_buf.push(BLOCK_RESET);
+        self.stats.output_bytes += 1;
+        Ok(())
+    }
+
+    /// Fails if `len` more bytes of input would overwrite any of the window with a
+    /// `strict_window`, counting the lookahead that has yet to be written into it.
+    fn check_window(&self, len: usize) -> Result<()> {
+        if !self.strict_window {
+            return Ok(());
+        }
+
+        // The window starts over every `block_size` bytes of input, and only the first one holds
+        // the preset dictionary.
+        let input = self.reset_input + self.lookahead.len() + len;
+        let preset = self.pos - self.reset_input;
+        let fits = match self.block_size {
+            Some(n) => {
+                preset + input.min(n) <= self.dict_size && (input <= n || n <= self.dict_size)
+            }
+            None => preset + input <= self.dict_size,
+        };
+        if fits {
+            Ok(())
+        } else {
+            Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("The input doesn't fit in the window of {} bytes.", self.dict_size),
+            ))
+        }
+    }
+
+    /// Empties the window, so that nothing after this matches anything before it.
+    fn clear_window(&mut self) {
+        self.dict.clear();
+        self.pos = 0;
+        self.finder.reset();
+        self.reset_input = 0;
+    }
+
+    /// Encodes the token that consumed `input`, ending the block once it fills up, and marking the
+    /// start of the next one once the window starts over.
+    fn encode(&mut self, tok: Token, input: &[u8]) -> Result<()> {
+        self.block_input.extend_from_slice(input);
+        self.write_token(&tok)?;
+        if self.block_input.len() >= BLOCK_SIZE {
+            self.end_block()?;
+        }
+        if self.block_size == Some(self.reset_input) {
+            self.mark_reset()?;
+        }
+        if self.high_water_mark.is_some_and(|mark| self.write_buf.len() > mark) {
+            self.write_finished()?;
+        }
+        Ok(())
+    }
+
+    /// Writes everything before the current block to the inner writer. The current block stays
+    /// buffered, since it may still be replaced by a stored block when it ends.
+    fn write_finished(&mut self) -> Result<()> {
+        let end = self.block_start.unwrap_or(self.write_buf.len());
+        self.inner.write_all(&self.write_buf[..end])?;
+        self.write_buf.drain(..end);
+
+        if let Some(start) = &mut self.block_start {
+            *start -= end;
+            self.ctrl -= end;
+        }
+        Ok(())
+    }
+
+    /// Ends the current block of tokens with the end marker, if there is one. If storing its input
+    /// as it is takes less space than the tokens, the block is replaced with a stored block.
+    fn end_block(&mut self) -> Result<()> {
+        let start = match self.block_start {
+            Some(start) => start,
+            None => return Ok(()),
+        };
+        self.write_token(&END)?;
+        self.block_start = None;
+        self.ctrl_bit = 0;
+
+        let len = self.block_input.len();
+        let stored_len = 1 + varint_len(len as u64) + len;
+        let tokens_len = self.write_buf.len() - start;
+        if stored_len < tokens_len {
+            self.write_buf.truncate(start);
+            self.write_buf.push(BLOCK_STORED);
+            write_varint(&mut self.write_buf, len as u64);
+            self.write_buf.extend_from_slice(&self.block_input);
 
-        let mut last = None;
-        while let Some(match_index) = self.next_match_index(last, lookahead) {
-            let len = self.match_len(match_index, lookahead);
+            self.stats.output_bytes -= (tokens_len - stored_len) as u64;
+            self.stats.stored_blocks += 1;
+        }
+
+        self.block_input.clear();
+        Ok(())
+    }
 
-            if len > best_match.0 {
-                best_match = (len, Some(match_index))
+    /// Tokenizes the lookahead until fewer than `min_lookahead` bytes are left in it, passing
+    /// each token to `emit` along with the bytes it consumed, or until `emit` returns false.
+    ///
+    /// A token can use up to `max_match + HASH_LEN` bytes of lookahead: the lazy match starts a
+    /// byte later, and its last position is hashed with the bytes following it. Tokenizing with
+    /// less lookahead than that may miss matches.
+    ///
+    /// With optimal parsing, `BLOCK_SIZE` bytes are tokenized at a time, so that many more bytes
+    /// are left in the lookahead until it is tokenized with a `min_lookahead` of 1.
+    ///
+    /// With a `block_size`, no token reaches past the end of the block, and the window is cleared
+    /// after the token that ends it.
+    fn parse<E>(&mut self, min_lookahead: usize, mut emit: E) -> Result<()>
+    where
+        E: FnMut(&mut Self, Token, &[u8]) -> Result<bool>,
+    {
+        let mut lookahead = std::mem::take(&mut self.lookahead);
+        let min_lookahead = min_lookahead.max(1);
+
+        let mut start = 0;
+        let mut res = Ok(());
+        'parse: while lookahead.len() - start >= min_lookahead {
+            // Matching stops at the end of the block, so nothing past it is needed.
+            let left = self.block_size.map_or(usize::MAX, |n| n - self.reset_input);
+            let end = lookahead.len().min(start.saturating_add(left));
+
+            let tokens = if self.optimal {
+                // Every position of the chunk gets at least `min_lookahead` bytes to match.
+                let chunk_len = BLOCK_SIZE.min(left);
+                let chunk_len = if min_lookahead == 1 {
+                    chunk_len.min(end - start)
+                } else if end - start >= left || end - start >= chunk_len + min_lookahead - 1 {
+                    chunk_len
+                } else {
+                    break;
+                };
+                self.parse_optimal(&lookahead[start..end], chunk_len)
+            } else {
+                self.parse_token(&lookahead[start..end]).map(|token| vec![token])
+            };
+            let tokens = match tokens {
+                Ok(tokens) => tokens,
+                Err(err) => {
+                    res = Err(err);
+                    break;
+                }
+            };
+
+            for (consumed, tok) in tokens {
+                let input = &lookahead[start..start + consumed];
+                start += consumed;
+                self.reset_input += consumed;
+
+                let more = emit(self, tok, input);
+                if self.block_size == Some(self.reset_input) {
+                    self.clear_window();
+                }
+                match more {
+                    Ok(true) => {}
+                    Ok(false) => break 'parse,
+                    Err(err) => {
+                        res = Err(err);
+                        break 'parse;
+                    }
+                }
             }
+        }
 
-            last = Some(match_index);
+        lookahead.drain(..start);
+        self.lookahead = lookahead;
+        res
+    }
+
+    /// Chooses the tokens for the first `len` bytes of the lookahead, writing them into the
+    /// dictionary, such that they take the fewest bits to encode.
+    ///
+    /// The longest match at each position, along with every prefix of it, is an edge ahead to a
+    /// later position, as is a literal. The cheapest path from the start to `len` is found by
+    /// relaxing the edges in order, since they only go forwards. The cost of a token includes its
+    /// bit of the control byte, so the path is never worse than the greedy one, which it contains.
+    fn parse_optimal(&mut self, lookahead: &[u8], len: usize) -> Result<Vec<(usize, Token)>> {
+        // The cost of getting to each position, and the token that gets there from where.
+        let mut cost = vec![u64::MAX; len + 1];
+        let mut from = vec![(0, END); len + 1];
+        cost[0] = 0;
+
+        for i in 0..len {
+            let (_, tok) = self.next_token(&lookahead[i..])?;
+            self.write_to_dictionary(&lookahead[i..], 1);
+
+            let literal = Token::Literal { byte: lookahead[i] };
+            let literal_cost = cost[i] + bit_cost(&literal);
+            if literal_cost < cost[i + 1] {
+                cost[i + 1] = literal_cost;
+                from[i + 1] = (i, literal);
+            }
+
+            if let Token::Rep { distance, length } = tok {
+                // The path has to end exactly at `len`.
+                for length in self.min_match.max(1)..=length.min(len - i) {
+                    let rep = Token::Rep { distance, length };
+                    let rep_cost = cost[i] + bit_cost(&rep);
+                    if rep_cost < cost[i + length] {
+                        cost[i + length] = rep_cost;
+                        from[i + length] = (i, rep);
+                    }
+                }
+            }
+        }
+
+        let mut tokens = vec![];
+        let mut at = len;
+        while at > 0 {
+            let (prev, tok) = from[at];
+            tokens.push((at - prev, tok));
+            at = prev;
+        }
+        tokens.reverse();
+
+        for (consumed, tok) in &tokens {
+            trace_token(*consumed, tok);
         }
+        Ok(tokens)
+    }
 
+    /// Chooses the token for the start of the lookahead and writes the bytes it consumes into the
+    /// dictionary.
+    fn parse_token(&mut self, lookahead: &[u8]) -> Result<(usize, Token)> {
+        let (mut consumed, mut tok) = self.next_token(lookahead)?;
 
-        if let (len, Some(index)) = best_match {
-            (len,
+        // With lazy matching, the match is deferred if the one starting at the next byte is
+        // strictly longer. That byte has to be in the dictionary for the next match to be found.
+        let mut inserted = 0;
+        if self.lazy && matches!(tok, Token::Rep { .. }) && lookahead.len() > 1 {
+            self.write_to_dictionary(lookahead, 1);
+            inserted = 1;
+
+            let (next_len, _) = self.next_token(&lookahead[1..])?;
+            if next_len > consumed {
+                consumed = 1;
+                tok = Token::Literal {
+                    byte: lookahead[0],
+                };
+            }
+        }
+        trace_token(consumed, &tok);
+
+        self.write_to_dictionary(&lookahead[inserted..], consumed - inserted);
+        Ok((consumed, tok))
+    }
+
+    fn next_token(&mut self, lookahead: &[u8]) -> Result<(usize, Token)> {
+        let span = trace_span!(
+            "next_token",
+            "dict.window" = tracing::field::Empty,
+            "dict.head" = tracing::field::Empty
+        );
+        self.record_dict(&span);
+        let _enter = span.enter();
+
+        if self.store_only {
+            return Ok((1, Token::Literal { byte: lookahead[0] }));
+        }
+
+        let (found, hops) = self.finder.find_with_hops(&self.dict, lookahead)?;
+        self.stats.searches += 1;
+        self.stats.chain_hops += hops as u64;
+
+        // The finder only reaches `dict_size` bytes back, however large the window itself is, so
+        // the reader always has the bytes a rep points at.
+        if let Some((distance, _)) = found {
+            debug_assert!(distance < self.dict_size, "Distance {} is out of the window.", distance);
+        }
+
+        // An empty match would be the end marker, even if `min_match` is 0.
+        Ok(match found {
+            Some((distance, len)) if len >= self.min_match.max(1) => (len,
             Token::Rep {
                 length: len,
-                distance: self.distance(index)
-            })
-        } else {
-            (1,
+                distance,
+            }),
+            _ => (1,
             Token::Literal {
                 byte: lookahead[0],
-            })
+            }),
+        })
+    }
+
+    /// Encodes the token into the write buffer, starting a new block or token group if needed.
+    fn write_token(&mut self, tok: &Token) -> Result<()> {
+        let len = self.write_buf.len();
+        if self.block_start.is_none() {
+            self.block_start = Some(len);
+            self.write_buf.push(BLOCK_TOKENS);
+            self.ctrl_bit = 0;
+        }
+        if self.ctrl_bit == 0 {
+            self.ctrl = self.write_buf.len();
+            self.write_buf.push(0);
         }
+
+        match *tok {
+            Token::Literal { byte } => self.write_buf.push(byte),
+            Token::Rep { distance, length } => {
+                self.write_buf[self.ctrl] |= 1 << self.ctrl_bit;
+                write_varint(&mut self.write_buf, distance as u64);
+                write_varint(&mut self.write_buf, length as u64);
+            }
+        }
+
+        match *tok {
+            Token::Literal { .. } => self.stats.literal_count += 1,
+            Token::Rep { length: 0, .. } => {}
+            Token::Rep { length, .. } => {
+                self.stats.rep_count += 1;
+                self.stats.total_match_length += length as u64;
+            }
+        }
+        self.stats.output_bytes += (self.write_buf.len() - len) as u64;
+
+        self.ctrl_bit = (self.ctrl_bit + 1) % 8;
+        Ok(())
     }
 
-    fn write_token(&mut self, _tok: &Token) -> Result<()> {
-        Ok(()) 
+    /// Records a snapshot of the window into the `dict.*` fields of the span, for the debug UI.
+    /// The window is laid out like the underlying buffer of a [`RingBuf`], where the newest half
+    /// comes first and ends at the head.
+    #[cfg(feature = "trace")]
+    fn record_dict(&self, span: &Span) {
+        let (oldest, newest) = self.dict.as_slices();
+        // Fields can't hold bytes, so each byte is recorded as the char with the same value.
+        let window: String = newest.iter().chain(oldest).map(|&byte| byte as char).collect();
+        span.record("dict.window", &window.as_str());
+        span.record("dict.head", &(newest.len() as u64));
     }
 
-    /// Returns the maximum length match from the dictionary, starting at the dictionary index
-    /// `at`.
-    fn match_len(&self, at: usize, lookahead: &[u8]) -> usize {
-        // The length of the match
-        let mut len = 0;
+    #[cfg(not(feature = "trace"))]
+    fn record_dict(&self, _span: &Span) {}
+
+    /// Writes the first `len` bytes of the lookahead into the dictionary. Each position is
+    /// inserted into the finder together with the bytes following it, so positions too close to
+    /// the end of the lookahead may not be matched against later.
+    fn write_to_dictionary(&mut self, lookahead: &[u8], len: usize) {
+        // The window grows before anything in it is overwritten, so every position the finder
+        // has is kept.
+        let needed = (self.dict.len() + len).min(self.max_window);
+        if needed > self.window_size {
+            self.window_size = needed.max(self.window_size * 2).min(self.max_window);
+            self.dict.grow(self.window_size);
+        }
 
-        // This is the maximum that len can get before it wraps over onto the output
-        // When we read from the dict, we have to mod len with over_eln before getting the offset.
-        // That way, our read will repeat as appropriate.
-        let over_len = self.distance(at) + 1;
+        self.finder.insert_many(self.pos, lookahead, len);
+        self.pos += len;
 
-        while self.dict[(at + (len % over_len)) % self.dict_size] == lookahead[len] {
-            len += 1;
+        // The window is in memory, so writing to it can't fail.
+        self.dict.write_all(&lookahead[..len]).unwrap();
+    }
+}
+
+/// The number of bits the token takes, including its bit of the control byte.
+fn bit_cost(tok: &Token) -> u64 {
+    8 * tok.encoded_len() as u64 + 1
+}
+
+/// Records the token's fields for the debug UI to pick up.
+#[cfg(feature = "trace")]
+fn trace_token(consumed: usize, tok: &Token) {
+    match *tok {
+        Token::Literal { byte } => {
+            trace!(consumed = consumed, token.byte = byte, "Produced a token.")
         }
+        Token::Rep { distance, length } => trace!(
+            consumed = consumed,
+            token.distance = distance,
+            token.length = length,
+            "Produced a token."
+        ),
+    }
+}
+
+#[cfg(not(feature = "trace"))]
+fn trace_token(_consumed: usize, _tok: &Token) {}
 
-        len
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::match_finder::{match_len, BruteForce};
+    use crate::test_util::{cycled, random_bytes, SEED};
+    use crate::{Reader, TokenReader, VecBuf};
+    use std::io::Read;
+
+    fn config() -> Config {
+        Config::builder()
+            .dict_size(0x80)
+            .max_chain_length(usize::MAX)
+            .build()
     }
 
-    fn next_match_index(&self, last: Option<usize>, lookahead: &[u8]) -> Option<usize> {
-        let index = if let Some(last) = last {
-            self.chain[last] as usize
-        } else {
-            self.map[lookahead[0] as usize] as usize
-        };
+    fn writer() -> Writer<Vec<u8>> {
+        Writer::new(Vec::new(), config())
+    }
 
-        if let Some(item) = self.dict.get(index) {
-            if *item == lookahead[0] {
-                Some(index)
-            } else {
-                None
+    /// Prepends the header for `config()` to the body of a stream.
+    fn framed(body: &[u8]) -> Vec<u8> {
+        let mut stream = vec![];
+        write_header(&mut stream, config().dict_size, config().checksum);
+        stream.extend_from_slice(body);
+        stream
+    }
+
+    /// The end marker, last block and trailer of a stream of `input`, which ends with a block of
+    /// tokens. The control bit of the end marker is left to the caller.
+    fn ended(input: &[u8]) -> [u8; 7] {
+        let mut checksum = Hasher::new(config().checksum);
+        checksum.update(input);
+        let [a, b, c, d] = checksum.finish().unwrap().to_be_bytes();
+        [0, 0, BLOCK_END, a, b, c, d]
+    }
+
+    /// Decodes the tokens of a compressed stream.
+    fn tokens(compressed: &[u8]) -> Result<Vec<Token>> {
+        let mut reader = Reader::new(compressed);
+        let mut tokens = vec![];
+        while let Some(tok) = reader.next_token()? {
+            tokens.push(tok);
+        }
+        Ok(tokens)
+    }
+
+    fn lit(byte: u8) -> Token {
+        Token::Literal { byte }
+    }
+
+    #[test]
+    fn test_write_token_banana() -> Result<()> {
+        let mut comp = writer();
+
+        // "banana banana"
+        let tokens = [
+            lit(b'b'),
+            lit(b'a'),
+            lit(b'n'),
+            Token::Rep { distance: 1, length: 3 },
+            lit(b' '),
+            Token::Rep { distance: 6, length: 6 },
+        ];
+        for tok in &tokens {
+            comp.write_token(tok)?;
+        }
+
+        assert_eq!(
+            framed(&[BLOCK_TOKENS, 0b0010_1000, b'b', b'a', b'n', 1, 3, b' ', 6, 6]),
+            comp.write_buf
+        );
+        Ok(())
+    }
+
+    /// Creates a writer with `bytes` already in its dictionary.
+    fn primed(config: Config, bytes: &[u8]) -> Writer<Vec<u8>> {
+        let mut comp = Writer::new(Vec::new(), config);
+        comp.write_to_dictionary(bytes, bytes.len());
+        comp
+    }
+
+    #[test]
+    fn test_match_consumes_lookahead() -> Result<()> {
+        let comp = primed(config(), b"abcabc");
+        assert_eq!(3, match_len(&comp.dict, 0, b"abc", comp.max_match)?);
+
+        let mut comp = writer();
+        comp.write_all(b"abcabcabcabc")?;
+        let mut expected = framed(&[BLOCK_TOKENS, 0b1_1000, b'a', b'b', b'c', 2, 9]);
+        expected.extend_from_slice(&ended(b"abcabcabcabc"));
+        assert_eq!(expected, comp.finish()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_min_match() -> Result<()> {
+        let mut comp = primed(config(), b"ab");
+        assert_eq!((1, lit(b'a')), comp.next_token(b"ab!")?);
+
+        let mut comp = primed(config(), b"abcd");
+        assert_eq!(
+            (4, Token::Rep { distance: 3, length: 4 }),
+            comp.next_token(b"abcd!")?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_lazy() -> Result<()> {
+        // At the second "a", greedy matching takes "abc" and is left with "de", which is too short
+        // to match. Lazy matching emits "a" as a literal and then matches all of "bcde".
+        let input = b"abc-bcde-abcde";
+        let lazy = || Writer::new(Vec::new(), Config { lazy: true, ..config() });
+
+        // The stream is short enough to be stored as it is, so the tokens are compared instead.
+        assert_eq!(12, writer().tokenize(input)?.len());
+        let tokens = lazy().tokenize(input)?;
+        assert_eq!(11, tokens.len());
+        assert_eq!(Some(&Token::Rep { distance: 5, length: 4 }), tokens.last());
+
+        let mut lazy = lazy();
+        lazy.write_all(input)?;
+        let lazy = lazy.finish()?;
+
+        let mut out = vec![];
+        Reader::new(&lazy[..]).read_to_end(&mut out)?;
+        assert_eq!(input[..], out[..]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_optimal() -> Result<()> {
+        let random: Vec<u8> = random_bytes(0x800, SEED).iter().map(|b| b & 0xf).collect();
+        let text = cycled(
+            b"It was the best of times, it was the worst of times, it was the age of wisdom, it \
+            was the age of foolishness, it was the epoch of belief, it was the epoch of \
+            incredulity. ",
+            3 * BLOCK_SIZE,
+        );
+        let corpus = [
+            b"abc-bcde-abcde".to_vec(),
+            cycled(b"Hey, banana-ass! To banana or not to banana? ", 0x400),
+            random.clone(),
+            [&random[..], &text, &random].concat(),
+            cycled(b"aaaaaaaaaab", 0x1000),
+            text,
+        ];
+
+        for input in &corpus {
+            let compressed = |config| -> Result<Vec<u8>> {
+                let mut comp = Writer::new(Vec::new(), config);
+                comp.write_all(input)?;
+                comp.finish()
+            };
+            let greedy = compressed(config())?;
+            let optimal = compressed(Config { optimal: true, ..config() })?;
+            assert!(
+                optimal.len() <= greedy.len(),
+                "Optimal parsing took {} bytes, but greedy parsing took {}.",
+                optimal.len(),
+                greedy.len()
+            );
+
+            let mut out = vec![];
+            Reader::new(&optimal[..]).read_to_end(&mut out)?;
+            assert_eq!(input, &out);
+        }
+
+        // The "a" is a literal, so that all of "bcde" can be matched.
+        let mut comp = Writer::new(Vec::new(), Config { optimal: true, ..config() });
+        let tokens = comp.tokenize(b"abc-bcde-abcde")?;
+        assert_eq!(Some(&Token::Rep { distance: 5, length: 4 }), tokens.last());
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_match() -> Result<()> {
+        let input = [b'a'; 100];
+
+        let mut comp = Writer::new(Vec::new(), Config { max_match: 16, ..config() });
+        comp.write_all(&input)?;
+        let compressed = comp.finish()?;
+
+        let tokens = tokens(&compressed)?;
+        assert_eq!(8, tokens.len());
+        for tok in tokens {
+            if let Token::Rep { length, .. } = tok {
+                assert!(length <= 16, "{:?} is longer than max_match.", tok);
             }
-        } else {
-            None
         }
+
+        let mut out = vec![];
+        Reader::new(&compressed[..]).read_to_end(&mut out)?;
+        assert_eq!(input[..], out[..]);
+        Ok(())
     }
 
-    /// Returns the index as the distance from the head, where 0 distance means the last item added
-    /// to the dictionary.
-    fn distance(&self, index: usize) -> usize {
-        if self.head > index {
-            self.head - index - 1
-        } else {
-            self.dict_size - index + self.head - 1
+    #[test]
+    fn test_grow_window() -> Result<()> {
+        // The prefix matches again long after the window would have been full at its first size.
+        let prefix = b"Hey, banana-ass! To banana or not to banana?";
+        let filler: Vec<u8> = (0..3 * INITIAL_WINDOW).map(|i| (i * 7 % 251) as u8).collect();
+        let input = [&prefix[..], &filler, &prefix[..]].concat();
+
+        let config = Config::builder().dict_size(4 * INITIAL_WINDOW).build();
+        let grown = Writer::new(Vec::new(), Config { grow_window: true, ..config })
+            .tokenize(&input)?;
+        let full = Writer::new(Vec::new(), config).tokenize(&input)?;
+        assert_eq!(full, grown);
+        assert_eq!(
+            Some(&Token::Rep { distance: input.len() - prefix.len() - 1, length: prefix.len() }),
+            grown.last()
+        );
+
+        // It grows no further than the furthest match.
+        let mut comp = Writer::new(Vec::new(), Config { max_distance: Some(0x1400), ..config });
+        comp.tokenize(&input)?;
+        assert_eq!(4 * INITIAL_WINDOW, comp.window_size);
+        let mut comp = Writer::new(
+            Vec::new(),
+            Config { max_distance: Some(0x1400), grow_window: true, ..config },
+        );
+        comp.tokenize(&input)?;
+        assert_eq!(0x1401, comp.window_size);
+        assert_eq!(0x2000, comp.dict.capacity());
+
+        let mut out = vec![];
+        let mut comp = Writer::new(Vec::new(), Config { grow_window: true, ..config });
+        comp.write_all(&input)?;
+        Reader::new(&comp.finish()?[..]).read_to_end(&mut out)?;
+        assert_eq!(input, out);
+        Ok(())
+    }
+
+    #[test]
+    fn test_tiny_dict() -> Result<()> {
+        // A window of 4 bytes wraps on almost every token, so the distances are always at the
+        // edge of it.
+        let input = b"abcdabcdXabcdabcaaaaaaabababXYXYXYabcdabcd";
+        let modes = [(false, false, false), (true, false, true), (false, true, false)];
+        for (lazy, optimal, grow_window) in modes {
+            let config = Config {
+                dict_size: 4,
+                min_match: 1,
+                lazy,
+                optimal,
+                grow_window,
+                ..config()
+            };
+            let mut comp = Writer::new(Vec::new(), config);
+            for chunk in input.chunks(3) {
+                comp.write_all(chunk)?;
+            }
+            let compressed = comp.finish()?;
+
+            for tok in tokens(&compressed)? {
+                if let Token::Rep { distance, .. } = tok {
+                    assert!(distance < 4, "{:?} with {}", tok, config);
+                }
+            }
+            let mut out = vec![];
+            Reader::new(&compressed[..]).read_to_end(&mut out)?;
+            assert_eq!(input[..], out[..], "{}", config);
         }
+        Ok(())
     }
 
-    fn write_to_dictionary(&mut self, bytes: &[u8]) {
-        for b in bytes {
-            let first_match = self.map[*b as usize];
-            self.map[*b as usize] = self.head as u32;
+    #[test]
+    fn test_max_chain_length() -> Result<()> {
+        let mut comp = primed(config(), b"abcd-abc-");
+        assert_eq!(
+            (4, Token::Rep { distance: 8, length: 4 }),
+            comp.next_token(b"abcd")?
+        );
 
-            if self.dict.len() < self.dict_size {
-                self.dict.push(*b);
-                self.chain.push(first_match);
-            } else {
-                self.dict[self.head] = *b;
-                self.chain[self.head] = first_match;
+        // Only the most recent "abc" is visited.
+        let mut comp = primed(Config { max_chain_length: 1, ..config() }, b"abcd-abc-");
+        assert_eq!(
+            (3, Token::Rep { distance: 3, length: 3 }),
+            comp.next_token(b"abcd")?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_distance() -> Result<()> {
+        // The closer "abc" is taken instead of the longer match that is out of range.
+        let mut comp = primed(Config { max_distance: Some(5), ..config() }, b"abcd-abc-");
+        assert_eq!(
+            (3, Token::Rep { distance: 3, length: 3 }),
+            comp.next_token(b"abcd")?
+        );
+
+        let mut comp = primed(Config { max_distance: Some(2), ..config() }, b"abcd-abc-");
+        assert_eq!((1, lit(b'a')), comp.next_token(b"abcd")?);
+
+        // The run of the last byte is always in range.
+        let mut comp = primed(Config { max_distance: Some(0), ..config() }, b"abc-");
+        assert_eq!(
+            (4, Token::Rep { distance: 0, length: 4 }),
+            comp.next_token(b"----")?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_hash_match() -> Result<()> {
+        let mut comp = primed(config(), b"xyzabc123");
+        assert_eq!(
+            (3, Token::Rep { distance: 5, length: 3 }),
+            comp.next_token(b"abc!")?
+        );
+        assert_eq!((1, lit(b'a')), comp.next_token(b"abd!")?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_out_of_step() -> Result<()> {
+        // The finder still has positions that the window has lost, which used to panic.
+        let mut comp = writer();
+        comp.write_all(b"To banana or not to banana?")?;
+        comp.flush()?;
+        comp.dict.clear();
+
+        comp.write_all(b" To banana or not to banana?")?;
+        let err = comp.flush().unwrap_err();
+        assert_eq!(std::io::ErrorKind::Other, err.kind());
+        assert_eq!("Position 26 is not in the window of 0 bytes.", err.to_string());
+        assert!(comp.tokenize(b"banana").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_wrap_overwritten() -> Result<()> {
+        let small = || Config { dict_size: 16, ..config() };
+
+        // "abc" is overwritten, but is still the last position with its hash.
+        let mut comp = primed(small(), b"abc0123456789ABCDEF");
+        assert_eq!(None, comp.finder.next_match_pos(None, b"abc"));
+        assert_eq!((1, lit(b'a')), comp.next_token(b"abc")?);
+
+        let input = b"abcdefgh-abcdefgh-0123456789-abcdefgh-0123456789";
+        let mut comp = Writer::new(Vec::new(), small());
+        comp.write_all(input)?;
+        let compressed = comp.finish()?;
+
+        for tok in tokens(&compressed)? {
+            if let Token::Rep { distance, .. } = tok {
+                assert!(distance < 16, "{:?} is outside of the dictionary.", tok);
             }
+        }
 
-            self.head = (self.head + 1) % self.dict_size;
+        let mut out = vec![];
+        Reader::new(&compressed[..]).read_to_end(&mut out)?;
+        assert_eq!(input[..], out[..]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_wrap_cycle() -> Result<()> {
+        let small = || Config { dict_size: 16, ..config() };
+
+        let input = cycled(b"0123456789abcdef", 0x400);
+        let mut comp = Writer::new(Vec::new(), small());
+        comp.write_all(&input)?;
+        let compressed = comp.finish()?;
+
+        let mut out = vec![];
+        Reader::new(&compressed[..]).read_to_end(&mut out)?;
+        assert_eq!(input, out);
+        Ok(())
+    }
+
+    #[test]
+    fn test_windows() -> Result<()> {
+        // The ring buffer rounds its capacity up, but the matches stay within `dict_size`.
+        let odd = || Config { dict_size: 100, ..config() };
+        let input = cycled(b"To banana or not to banana? ", 0x400);
+
+        let mut ring = Writer::<_, RingBuf>::with_window(Vec::new(), odd());
+        ring.write_all(&input)?;
+        let ring = ring.finish()?;
+
+        let mut vec = Writer::<_, VecBuf>::with_window(Vec::new(), odd());
+        vec.write_all(&input)?;
+        assert_eq!(ring, vec.finish()?);
+
+        let mut out = vec![];
+        Reader::new(&ring[..]).read_to_end(&mut out)?;
+        assert_eq!(input, out);
+        Ok(())
+    }
+
+    #[test]
+    fn test_finders() -> Result<()> {
+        let input = cycled(b"Hey, banana-ass! To banana or not to banana? ", 0x400);
+
+        // With an unbounded chain, the hash chains find the longest match just like brute force.
+        let mut brute_force = Writer::<_, RingBuf, BruteForce>::with_window(Vec::new(), config());
+        brute_force.write_all(&input)?;
+        let brute_force = brute_force.finish()?;
+        let mut hash_chain = writer();
+        hash_chain.write_all(&input)?;
+        assert_eq!(tokens(&brute_force)?, tokens(&hash_chain.finish()?)?);
+
+        let mut out = vec![];
+        Reader::new(&brute_force[..]).read_to_end(&mut out)?;
+        assert_eq!(input, out);
+        Ok(())
+    }
+
+    #[test]
+    fn test_buffered_writes() -> Result<()> {
+        let input = cycled(b"To banana or not to banana? ", 0x400);
+
+        let mut all = writer();
+        all.write_all(&input)?;
+
+        let mut bytewise = writer();
+        for byte in &input {
+            assert_eq!(1, bytewise.write(&[*byte])?);
         }
+
+        assert_eq!(tokens(&all.finish()?)?, tokens(&bytewise.finish()?)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_finish_tail() -> Result<()> {
+        let input = b"abcdabcdabcdXYZ";
+
+        let mut comp = writer();
+        comp.write_all(input)?;
+        let compressed = comp.finish()?;
+        assert_eq!(
+            Some(&[lit(b'X'), lit(b'Y'), lit(b'Z')][..]),
+            tokens(&compressed)?.rchunks(3).next()
+        );
+
+        let mut out = vec![];
+        Reader::new(&compressed[..]).read_to_end(&mut out)?;
+        assert_eq!(input[..], out[..]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_levels() -> Result<()> {
+        let input = cycled(
+            b"It was the best of times, it was the worst of times, it was the age of wisdom, it \
+            was the age of foolishness, it was the epoch of belief, it was the epoch of \
+            incredulity, it was the season of Light, it was the season of Darkness. ",
+            0x2000,
+        );
+
+        let mut counts = vec![];
+        for level in 0..=9 {
+            let mut comp = Writer::new(Vec::new(), Config::level(level));
+            comp.write_all(&input)?;
+            let compressed = comp.finish()?;
+            counts.push(tokens(&compressed)?.len());
+
+            let mut out = vec![];
+            Reader::new(&compressed[..]).read_to_end(&mut out)?;
+            assert_eq!(input, out, "Level {} did not roundtrip.", level);
+        }
+
+        assert_eq!(input.len(), counts[0]);
+        for (level, pair) in counts.windows(2).enumerate() {
+            assert!(
+                pair[1] <= pair[0],
+                "Level {} produced more tokens than level {}: {:?}",
+                level + 1,
+                level,
+                counts
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_store_only() -> Result<()> {
+        let input = cycled(b"To banana or not to banana? ", 0x400);
+
+        let mut comp = Writer::new(Vec::new(), Config { store_only: true, ..config() });
+        comp.write_all(&input)?;
+        comp.flush()?;
+
+        // The window is kept, even though nothing matches against it.
+        assert_eq!(input[input.len() - 0x80..], comp.dict.to_vec()[..]);
+        assert!(comp.finder.find(&comp.dict, b"banana")?.is_some());
+        assert_eq!(input.len() as u64, comp.stats().literal_count);
+
+        // Literals cost a control bit each, so the block is stored instead.
+        let compressed = comp.finish()?;
+        let stored_len = 1 + varint_len(input.len() as u64) + input.len();
+        assert_eq!(framed(&[]).len() + stored_len + 5, compressed.len());
+        assert!(tokens(&compressed)?.iter().all(|tok| matches!(tok, Token::Literal { .. })));
+
+        let mut out = vec![];
+        Reader::new(&compressed[..]).read_to_end(&mut out)?;
+        assert_eq!(input, out);
+        Ok(())
+    }
+
+    #[test]
+    fn test_strict_window() -> Result<()> {
+        let input = cycled(b"To banana or not to banana? ", 0x200);
+        let strict = Config { strict_window: true, ..config() };
+
+        // The input fails as soon as it would overwrite the window, without being written.
+        let mut comp = Writer::new(Vec::new(), strict);
+        comp.write_all(&input[..0x60])?;
+        assert_eq!(ErrorKind::InvalidInput, comp.write(&input[0x60..0x81]).unwrap_err().kind());
+        comp.write_all(&input[0x60..0x80])?;
+        assert_eq!(ErrorKind::InvalidInput, comp.write(b"!").unwrap_err().kind());
+        assert_eq!(ErrorKind::InvalidInput, comp.tokenize(b"!").unwrap_err().kind());
+
+        let mut out = vec![];
+        Reader::new(&comp.finish()?[..]).read_to_end(&mut out)?;
+        assert_eq!(input[..0x80], out[..]);
+
+        // The preset dictionary takes up the window too.
+        let mut comp = Writer::new(Vec::new(), strict);
+        comp.set_dictionary(&input[..0x10]);
+        assert!(comp.write(&input[..0x71]).is_err());
+
+        // Each block starts over with an empty window.
+        let mut comp = Writer::new(Vec::new(), Config { block_size: Some(0x80), ..strict });
+        comp.write_all(&input)?;
+        let mut out = vec![];
+        Reader::new(&comp.finish()?[..]).read_to_end(&mut out)?;
+        assert_eq!(input, out);
+
+        let mut comp = Writer::new(Vec::new(), Config { block_size: Some(0x100), ..strict });
+        assert!(comp.write(&input[..0x81]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_config_errors() {
+        let err = |config| Writer::try_new(Vec::new(), config).unwrap_err();
+
+        assert_eq!(ConfigError::DictZero, err(Config { dict_size: 0, ..config() }));
+        assert_eq!(ConfigError::BlockZero, err(Config { block_size: Some(0), ..config() }));
+        assert_eq!(
+            ConfigError::MatchRange { min_match: 8, max_match: 4 },
+            err(Config { min_match: 8, max_match: 4, ..config() })
+        );
+
+        #[cfg(target_pointer_width = "64")]
+        assert_eq!(
+            ConfigError::DictTooLarge(1 << 32),
+            err(Config { dict_size: 1 << 32, ..config() })
+        );
+
+        assert!(Writer::try_new(Vec::new(), config()).is_ok());
+    }
+
+    #[test]
+    fn test_default_config() -> Result<()> {
+        let config = Config::default();
+        assert_eq!(0x8000, config.dict_size);
+        let input = b"To banana or not to banana? To banana or not to banana?";
+
+        let mut comp = Writer::new(Vec::new(), Config { lazy: true, ..Default::default() });
+        comp.write_all(input)?;
+        let compressed = comp.finish()?;
+        assert!(compressed.len() < input.len());
+
+        let mut out = vec![];
+        Reader::new(&compressed[..]).read_to_end(&mut out)?;
+        assert_eq!(input[..], out[..]);
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic(expected = "Dictionary must not be empty!")]
+    fn test_new_panics() {
+        Writer::new(Vec::new(), Config { dict_size: 0, ..config() });
+    }
+
+    #[test]
+    fn test_stats() -> Result<()> {
+        let input = b"Hey, banana-ass! To banana or not to banana? To banana or not to banana?";
+        let mut comp = writer();
+        comp.write_all(input)?;
+        comp.compress(1)?;
+
+        let stats = *comp.stats();
+        assert_eq!(comp.write_buf.len() as u64, stats.output_bytes);
+        let tokens = tokens(&comp.finish()?)?;
+        assert_eq!(input.len() as u64, stats.input_bytes);
+        assert_eq!(stats.literal_count + stats.rep_count, tokens.len() as u64);
+        assert_eq!(input.len() as u64, stats.literal_count + stats.total_match_length);
+        assert_ne!(0, stats.rep_count);
+        assert!(stats.ratio() > 1.0);
+
+        // There is nothing to match in random bytes.
+        let random = random_bytes(0x400, SEED);
+        let mut comp = writer();
+        comp.write_all(&random)?;
+        comp.compress(1)?;
+        assert_eq!(0, comp.stats().rep_count);
+        assert_eq!(0x400, comp.stats().literal_count);
+        assert!(comp.stats().ratio() < 1.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_chain_hops() -> Result<()> {
+        let chain_length = |input: &[u8]| -> Result<f64> {
+            let mut comp = Writer::new(Vec::new(), Config::default());
+            comp.write_all(input)?;
+            comp.compress(1)?;
+            Ok(comp.stats().average_chain_length())
+        };
+
+        // Every position starts with one of a few prefixes, so they share hashes, but the byte
+        // after the prefix varies.
+        let random = random_bytes(0x400, SEED);
+        let colliding: Vec<u8> =
+            random[..0x100].iter().flat_map(|&byte| [b'a', b'b', b'c', byte]).collect();
+
+        let random = chain_length(&random)?;
+        let colliding = chain_length(&colliding)?;
+        assert!(random < 0.1, "{}", random);
+        assert!(colliding > 10.0 * random.max(1.0), "{} vs {}", colliding, random);
+        Ok(())
+    }
+
+    #[test]
+    fn test_reencode() -> Result<()> {
+        let input = b"Hey, banana-ass! To banana or not to banana? To banana or not to banana?";
+        let mut comp = writer();
+        comp.write_all(input)?;
+        let compressed = comp.finish()?;
+
+        // Encoding the decoded tokens again gives back the same tokens, up to the last block.
+        let tokens = TokenReader::new(&compressed[..]).collect::<Result<Vec<_>>>()?;
+        let mut comp = writer();
+        for tok in tokens.iter().chain([&END]) {
+            comp.write_token(tok)?;
+        }
+        assert_eq!(compressed[..compressed.len() - 5], comp.write_buf[..]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_tokenize() -> Result<()> {
+        let mut comp = writer();
+        assert_eq!(
+            vec![lit(b'a'), lit(b'X'), lit(b'a'), lit(b'X')],
+            comp.tokenize(b"aXaX")?
+        );
+
+        // The dictionary is kept, so this matches the previous input.
+        assert_eq!(
+            vec![Token::Rep { distance: 3, length: 4 }, lit(b'!')],
+            comp.tokenize(b"aXaX!")?
+        );
+
+        let input = b"Hey, banana-ass! To banana or not to banana?";
+        let mut comp = writer();
+        comp.write_all(input)?;
+        assert_eq!(tokens(&comp.finish()?)?, writer().tokenize(input)?);
+
+        // What was written before is encoded, and only the tokenized bytes are left out.
+        let mut comp = writer();
+        comp.write_all(b"hello ")?;
+        assert_eq!(5, comp.tokenize(b"world")?.len());
+        let mut out = vec![];
+        Reader::new(&comp.finish()?[..]).read_to_end(&mut out)?;
+        assert_eq!(b"hello "[..], out[..]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_tokens() -> Result<()> {
+        let input = b"Hey, banana-ass! To banana or not to banana? To banana or not to banana?";
+
+        let mut direct = writer();
+        direct.write_all(input)?;
+        let mut replayed = writer();
+        replayed.write_tokens(&writer().tokenize(input)?)?;
+        assert_eq!(replayed.stats().input_bytes, input.len() as u64);
+        assert_eq!(direct.finish()?, replayed.finish()?);
+
+        // The replayed tokens match each other and are matched against by later writes.
+        let mut comp = writer();
+        comp.write_tokens(&[lit(b'a'), lit(b'b'), Token::Rep { distance: 1, length: 6 }])?;
+        comp.write_all(b"abababab")?;
+        comp.write_tokens(&[Token::Rep { distance: 15, length: 2 }])?;
+        let compressed = comp.finish()?;
+        assert_eq!(
+            vec![
+                lit(b'a'),
+                lit(b'b'),
+                Token::Rep { distance: 1, length: 6 },
+                Token::Rep { distance: 3, length: 8 },
+                Token::Rep { distance: 15, length: 2 },
+            ],
+            tokens(&compressed)?
+        );
+
+        let mut out = vec![];
+        Reader::new(&compressed[..]).read_to_end(&mut out)?;
+        assert_eq!(b"abababababababab"[..], out[..16]);
+        assert_eq!(b"ab"[..], out[16..]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_encoded_len() -> Result<()> {
+        let input = cycled(b"Hey, banana-ass! To banana or not to banana? ", 0x400);
+        let tokens = Writer::new(Vec::new(), Config { dict_size: 0x400, ..config() })
+            .tokenize(&input)?;
+        assert!(tokens.iter().any(|tok| tok.encoded_len() > 2));
+
+        let mut comp = Writer::new(Vec::new(), Config { dict_size: 0x400, ..config() });
+        comp.write_tokens(&tokens)?;
+        assert_eq!(0, comp.stats().stored_blocks);
+        let compressed = comp.finish()?;
+
+        // Everything else is the header, the block kind, the control bytes, the end marker, the
+        // last block and the trailer.
+        let tokens_len: usize = tokens.iter().map(Token::encoded_len).sum();
+        let ctrl_len = (tokens.len() + 1).div_ceil(8);
+        assert_eq!(
+            framed(&[]).len() + 1 + ctrl_len + tokens_len + END.encoded_len() + 1 + 4,
+            compressed.len()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_tokens_invalid() {
+        let mut comp = writer();
+        let kind = |res: Result<()>| res.unwrap_err().kind();
+        assert_eq!(ErrorKind::InvalidInput, kind(comp.write_tokens(&[END])));
+        assert_eq!(
+            ErrorKind::InvalidInput,
+            kind(comp.write_tokens(&[lit(b'a'), Token::Rep { distance: 1, length: 2 }]))
+        );
+        assert_eq!(
+            ErrorKind::InvalidInput,
+            kind(comp.write_tokens(&[Token::Rep { distance: 0x80, length: 2 }]))
+        );
+
+        // Nothing was encoded by the failed writes.
+        assert_eq!(0, comp.stats().input_bytes);
+        assert_eq!(framed(&[]), comp.write_buf);
+    }
+
+    #[test]
+    fn test_write_tokens_grown() -> Result<()> {
+        let config = Config {
+            dict_size: 0x8000,
+            max_distance: Some(1000),
+            grow_window: true,
+            ..Config::default()
+        };
+        let mut comp = Writer::new(Vec::new(), config);
+        comp.write_all(&[b'a'; 5000])?;
+
+        // The window only grew as far as the maximum distance reaches.
+        let kind = |res: Result<()>| res.unwrap_err().kind();
+        let far = Token::Rep { distance: 2000, length: 4 };
+        assert_eq!(ErrorKind::InvalidInput, kind(comp.write_tokens(&[far])));
+        let past = Token::Rep { distance: 1001, length: 4 };
+        assert_eq!(ErrorKind::InvalidInput, kind(comp.write_tokens(&[past])));
+        comp.write_tokens(&[Token::Rep { distance: 1000, length: 4 }])?;
+
+        let mut out = vec![];
+        Reader::new(&comp.finish()?[..]).read_to_end(&mut out)?;
+        assert_eq!(vec![b'a'; 5004], out);
+        Ok(())
+    }
+
+    #[test]
+    fn test_reset() -> Result<()> {
+        let first = b"To banana or not to banana?";
+        let second = b"abcabcabcabcabc";
+
+        let mut comp = writer();
+        comp.write_all(first)?;
+        let first_compressed = comp.reset(Vec::new())?;
+        comp.write_all(second)?;
+        let second_compressed = comp.finish()?;
+
+        // The second stream doesn't depend on the first one.
+        assert_eq!(writer().tokenize(second)?, tokens(&second_compressed)?);
+
+        let streams = [(&first[..], first_compressed), (&second[..], second_compressed)];
+        for (input, compressed) in streams {
+            let mut out = vec![];
+            Reader::new(&compressed[..]).read_to_end(&mut out)?;
+            assert_eq!(input, &out[..]);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_token_groups() -> Result<()> {
+        let mut comp = writer();
+        for byte in b"abcdefghi" {
+            comp.write_token(&lit(*byte))?;
+        }
+        comp.write_token(&Token::Rep { distance: 200, length: 4 })?;
+
+        let mut expected = framed(&[BLOCK_TOKENS, 0]);
+        expected.extend_from_slice(b"abcdefgh");
+        expected.extend_from_slice(&[0b10, b'i', 0xc8, 0x01, 4]);
+        assert_eq!(expected, comp.write_buf);
+        Ok(())
+    }
+
+    #[test]
+    fn test_stored_blocks() -> Result<()> {
+        // There is nothing to match in random bytes.
+        let random = random_bytes(0x400, SEED);
+
+        let mut comp = writer();
+        comp.write_all(&random)?;
+        comp.compress(1)?;
+        comp.end_block()?;
+        assert_eq!(1, comp.stats().stored_blocks);
+        assert_eq!(comp.write_buf.len() as u64, comp.stats().output_bytes);
+
+        // The header, the block kind and length, the last block and the trailer.
+        let compressed = comp.finish()?;
+        assert_eq!(framed(&[]).len() + 3 + random.len() + 5, compressed.len());
+        assert_eq!(BLOCK_STORED, compressed[framed(&[]).len()]);
+
+        let mut out = vec![];
+        Reader::new(&compressed[..]).read_to_end(&mut out)?;
+        assert_eq!(random, out);
+
+        // A block is ended every `BLOCK_SIZE` bytes. The random bytes end up in a block with
+        // plenty to match, so it isn't stored.
+        let text = cycled(b"To banana or not to banana? ", BLOCK_SIZE);
+        let input = [&text[..], &random, &text].concat();
+        let mut comp = Writer::new(Vec::new(), Config { dict_size: 0x400, ..config() });
+        comp.write_all(&input)?;
+        comp.compress(1)?;
+        comp.end_block()?;
+        assert_eq!(0, comp.stats().stored_blocks);
+        let compressed = comp.finish()?;
+
+        let mut out = vec![];
+        Reader::new(&compressed[..]).read_to_end(&mut out)?;
+        assert_eq!(input, out);
+        Ok(())
+    }
+
+    #[test]
+    fn test_high_water_mark() -> Result<()> {
+        let input = cycled(b"Hey, banana-ass! To banana or not to banana? ", 4 * BLOCK_SIZE);
+
+        let mut comp = Writer::new(Vec::new(), Config { high_water_mark: Some(0x100), ..config() });
+        comp.write_all(&input[..BLOCK_SIZE + 1])?;
+        let written = comp.get_ref().len();
+        assert_ne!(0, written);
+
+        // Only whole blocks are written, and the rest stays bounded by the block being encoded.
+        comp.write_all(&input[BLOCK_SIZE + 1..])?;
+        assert!(comp.get_ref().len() > written);
+        assert!(comp.write_buf.len() <= 0x100 + BLOCK_SIZE);
+        assert_eq!(
+            comp.stats().output_bytes,
+            (comp.get_ref().len() + comp.write_buf.len()) as u64
+        );
+
+        let compressed = comp.finish()?;
+        let mut expected = writer();
+        expected.write_all(&input)?;
+        assert_eq!(expected.finish()?, compressed);
+
+        let mut out = vec![];
+        Reader::new(&compressed[..]).read_to_end(&mut out)?;
+        assert_eq!(input, out);
+        Ok(())
+    }
+
+    #[test]
+    fn test_inner() -> Result<()> {
+        let input = b"To banana or not to banana?";
+        let mut comp = writer();
+        comp.write_all(input)?;
+        assert!(comp.get_ref().is_empty());
+
+        // Nothing is flushed, not even the header.
+        assert!(comp.into_inner().is_empty());
+
+        let mut comp = writer();
+        comp.get_mut().extend_from_slice(b"prefix");
+        comp.write_all(input)?;
+        let compressed = comp.finish()?;
+        assert_eq!(b"prefix"[..], compressed[..6]);
+
+        let mut out = vec![];
+        Reader::new(&compressed[6..]).read_to_end(&mut out)?;
+        assert_eq!(input[..], out[..]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_by_ref() -> Result<()> {
+        let input = b"To banana or not to banana? To banana or not to banana?";
+        let mut compressed = b"prefix".to_vec();
+
+        let mut comp = Writer::by_ref(&mut compressed, config());
+        comp.write_all(input)?;
+        comp.finish()?;
+
+        // The stream follows whatever was already there, and the writer can be written to again.
+        compressed.extend_from_slice(b"suffix");
+        assert_eq!(b"prefix"[..], compressed[..6]);
+        assert_eq!(b"suffix"[..], compressed[compressed.len() - 6..]);
+
+        let mut out = vec![];
+        Reader::new(&compressed[6..]).read_to_end(&mut out)?;
+        assert_eq!(input[..], out[..]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_compress_str() -> Result<()> {
+        let mut comp = writer();
+        comp.compress_str("Hey, banana-ass! ")?;
+        comp.compress_str("To banana or not to banana? ¿Plátano?")?;
+        let compressed = comp.finish()?;
+
+        let mut comp = writer();
+        let fruit = "Plátano";
+        write!(comp, "Hey, banana-ass! To banana or not to banana? ¿{}?", fruit)?;
+        assert_eq!(comp.finish()?, compressed);
+        Ok(())
+    }
+
+    #[test]
+    fn test_fmt_write() -> Result<()> {
+        fn format<W: fmt::Write>(w: &mut W, fruit: &str) -> fmt::Result {
+            for i in 0..50 {
+                write!(w, "{} {}, ", i, fruit)?;
+            }
+            Ok(())
+        }
+        let text: String = (0..50).map(|i| format!("{} banana, ", i)).collect();
+
+        let mut comp = writer();
+        format(&mut comp, "banana").unwrap();
+        assert!(comp.take_fmt_error().is_none());
+        let compressed = comp.finish()?;
+
+        let mut out = vec![];
+        Reader::new(&compressed[..]).read_to_end(&mut out)?;
+        assert_eq!(text.as_bytes(), &out[..]);
+
+        // The header doesn't fit, which is only found out once the output is written.
+        let mut buf = [0; 4];
+        let mut comp = Writer::new(&mut buf[..], Config { high_water_mark: Some(0), ..config() });
+        assert_eq!(Err(fmt::Error), format(&mut comp, "banana"));
+        assert_eq!(ErrorKind::WriteZero, comp.take_fmt_error().unwrap().kind());
+        assert!(comp.take_fmt_error().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_error() -> Result<()> {
+        let input = cycled(b"To banana or not to banana? ", 0x400);
+
+        // The header doesn't fit, but the bytes the failing write took are only taken once.
+        let mut buf = [0; 4];
+        let mut comp = Writer::new(&mut buf[..], Config { high_water_mark: Some(0), ..config() });
+        comp.write_all(&input)?;
+        assert_eq!(input.len() as u64, comp.stats().input_bytes);
+
+        assert_eq!(ErrorKind::WriteZero, comp.write(b"banana").unwrap_err().kind());
+        assert_eq!(input.len() as u64, comp.stats().input_bytes);
+
+        // The inner writer is still full.
+        assert_eq!(ErrorKind::WriteZero, comp.flush().unwrap_err().kind());
+        Ok(())
+    }
+
+    #[test]
+    fn test_flush() -> Result<()> {
+        let input = b"To banana or not to banana? To banana or not to banana?";
+        let mut comp = writer();
+        comp.write_all(&input[..30])?;
+        comp.flush()?;
+        let flushed = comp.get_ref().clone();
+        comp.flush()?;
+        assert_eq!(&flushed, comp.get_ref());
+
+        // Everything written so far can be decoded, up to the missing end of the stream.
+        let mut out = vec![];
+        let err = Reader::new(&flushed[..]).read_to_end(&mut out).unwrap_err();
+        assert_eq!(std::io::ErrorKind::UnexpectedEof, err.kind());
+        assert_eq!(input[..30], out[..]);
+
+        comp.write_all(&input[30..])?;
+        let compressed = comp.finish()?;
+        assert_eq!(flushed[..], compressed[..flushed.len()]);
+
+        let mut out = vec![];
+        Reader::new(&compressed[..]).read_to_end(&mut out)?;
+        assert_eq!(input[..], out[..]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sync_flush() -> Result<()> {
+        let input = b"To banana or not to banana? To banana or not to banana?";
+        let mut comp = writer();
+        comp.write_all(&input[..30])?;
+        comp.sync_flush()?;
+        let flushed = comp.get_ref().clone();
+        assert_eq!(Some(&BLOCK_SYNC), flushed.last());
+
+        // The reader gets to the marker, and only fails when it looks past it.
+        let mut reader = Reader::new(&flushed[..]);
+        let mut out = vec![0; 30];
+        reader.read_exact(&mut out)?;
+        assert_eq!(input[..30], out[..]);
+        assert_eq!(std::io::ErrorKind::UnexpectedEof, reader.read(&mut [0]).unwrap_err().kind());
+
+        comp.write_all(&input[30..])?;
+        comp.sync_flush()?;
+        let compressed = comp.finish()?;
+
+        let mut out = vec![];
+        Reader::new(&compressed[..]).read_to_end(&mut out)?;
+        assert_eq!(input[..], out[..]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_block_size() -> Result<()> {
+        let input = cycled(b"To banana or not to banana? ", 0x1c0);
+
+        for optimal in [false, true] {
+            let config = Config::builder()
+                .dict_size(0x80)
+                .block_size(0x80)
+                .optimal(optimal)
+                .build();
+            let mut comp = Writer::new(Vec::new(), config);
+            comp.write_all(&input)?;
+            comp.flush()?;
+            let points = comp.seek_points().to_vec();
+            let compressed = comp.finish()?;
+
+            let inputs: Vec<u64> = points.iter().map(|point| point.input).collect();
+            assert_eq!(vec![0x80, 0x100, 0x180], inputs);
+            for point in &points {
+                assert_eq!(BLOCK_RESET, compressed[point.output as usize]);
+            }
+
+            // No token reaches back before the start of its block, or on past its end.
+            let mut at = 0;
+            for tok in TokenReader::new(&compressed[..]) {
+                match tok? {
+                    Token::Literal { .. } => at += 1,
+                    Token::Rep { distance, length } => {
+                        assert!(distance < at % 0x80);
+                        assert!(at % 0x80 + length <= 0x80);
+                        at += length;
+                    }
+                }
+            }
+            assert_eq!(input.len(), at);
+
+            let mut out = vec![];
+            Reader::new(&compressed[..]).read_to_end(&mut out)?;
+            assert_eq!(input, out);
+        }
+
+        let mut comp = Writer::new(Vec::new(), Config::builder().block_size(0x80).build());
+        let err = comp.write_tokens(&[lit(b'a')]).unwrap_err();
+        assert_eq!(ErrorKind::InvalidInput, err.kind());
+        Ok(())
+    }
+
+    #[test]
+    fn test_run() -> Result<()> {
+        // The "a" of the dictionary is too short to be hashed, but the run still matches it.
+        let mut comp = writer();
+        comp.set_dictionary(b"a");
+        assert_eq!(vec![Token::Rep { distance: 0, length: 200 }], comp.tokenize(&[b'a'; 200])?);
+
+        // The same goes for the last byte before a flush.
+        let mut comp = writer();
+        comp.write_all(b"xyz")?;
+        comp.flush()?;
+        assert_eq!(vec![Token::Rep { distance: 0, length: 100 }], comp.tokenize(&[b'z'; 100])?);
+
+        let input = [&b"xyz"[..], &[b'z'; 100]].concat();
+        let mut comp = writer();
+        comp.write_all(&input[..3])?;
+        comp.flush()?;
+        comp.write_all(&input[3..])?;
+        let compressed = comp.finish()?;
+
+        let mut out = vec![];
+        Reader::new(&compressed[..]).read_to_end(&mut out)?;
+        assert_eq!(input, out);
+        Ok(())
+    }
+
+    #[test]
+    fn test_compress_bounded() -> Result<()> {
+        let input: Vec<u8> = (0..0x800u32)
+            .map(|i| b"To banana or not to banana? "[(i % 28) as usize] ^ (i % 251 == 0) as u8)
+            .collect();
+
+        // Nothing fits, but the header is written anyway.
+        let mut comp = writer();
+        assert_eq!((0, 0), comp.compress_bounded(&input, 8)?);
+        let header_len = comp.get_ref().len();
+        assert_eq!(framed(&[])[..], comp.get_ref()[..]);
+
+        let mut left = &input[..];
+        let mut calls = 0;
+        while !left.is_empty() {
+            let before = comp.get_ref().len();
+            let (consumed, produced) = comp.compress_bounded(left, 32)?;
+            assert!(consumed > 0 && produced <= 32);
+            assert_eq!(before + produced, comp.get_ref().len());
+            left = &left[consumed..];
+            calls += 1;
+        }
+        assert!(calls > 1);
+        assert_eq!(input.len() as u64, comp.stats().input_bytes);
+
+        // Writing carries on from the bounded calls as well.
+        comp.write_all(b"banana")?;
+        let compressed = comp.finish()?;
+        assert!(compressed.len() > header_len);
+        let mut out = vec![];
+        Reader::new(&compressed[..]).read_to_end(&mut out)?;
+        assert_eq!([&input[..], b"banana"].concat(), out);
+        Ok(())
     }
 }
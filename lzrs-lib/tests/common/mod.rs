@@ -0,0 +1,6 @@
+//! Inputs shared by the integration tests, which can't see the crate's own `test_util`.
+
+/// Repeats `text` until it is `len` bytes long, so that there is plenty to match.
+pub fn cycled(text: &[u8], len: usize) -> Vec<u8> {
+    text.iter().copied().cycle().take(len).collect()
+}
@@ -0,0 +1,76 @@
+use lzrs_lib::{compress, decompress, Checksum, Config};
+use proptest::prelude::*;
+use std::io::Cursor;
+
+/// How a piece of the input is generated from a byte and a length.
+#[derive(Clone, Debug)]
+enum Piece {
+    Literal(u8),
+    Run(u8, usize),
+    /// Copies the last `distance` bytes of the input so far until `length` bytes are copied.
+    Repeat(usize, usize),
+}
+
+/// Inputs built out of pieces, so most of them have something to match and shrink into smaller
+/// pieces.
+fn input() -> impl Strategy<Value = Vec<u8>> {
+    let piece = prop_oneof![
+        any::<u8>().prop_map(Piece::Literal),
+        (any::<u8>(), 1..300usize).prop_map(|(byte, len)| Piece::Run(byte, len)),
+        (1..300usize, 1..600usize).prop_map(|(distance, len)| Piece::Repeat(distance, len)),
+    ];
+
+    prop::collection::vec(piece, 0..64).prop_map(|pieces| {
+        let mut out: Vec<u8> = vec![];
+        for piece in pieces {
+            match piece {
+                Piece::Literal(byte) => out.push(byte),
+                Piece::Run(byte, len) => out.extend(std::iter::repeat_n(byte, len)),
+                Piece::Repeat(distance, len) => {
+                    let start = out.len().saturating_sub(distance);
+                    for i in 0..if start < out.len() { len } else { 0 } {
+                        out.push(out[start + i]);
+                    }
+                }
+            }
+        }
+        out
+    })
+}
+
+fn config() -> impl Strategy<Value = Config> {
+    (
+        1..0x1000usize,
+        0..8usize,
+        0..300usize,
+        0..64usize,
+        any::<bool>(),
+        any::<bool>(),
+        prop_oneof![Just(Checksum::None), Just(Checksum::Adler32), Just(Checksum::Crc32)],
+    )
+        .prop_map(|(dict_size, min_match, extra, max_chain_length, lazy, optimal, checksum)| {
+            Config::builder()
+                .dict_size(dict_size)
+                .min_match(min_match)
+                .max_match(min_match + extra)
+                .max_chain_length(max_chain_length)
+                .lazy(lazy)
+                .optimal(optimal)
+                .checksum(checksum)
+                .build()
+        })
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(256))]
+
+    #[test]
+    fn test_roundtrip(input in input(), config in config()) {
+        let mut compressed = vec![];
+        compress(Cursor::new(&input), &mut compressed, config)?;
+
+        let mut out = vec![];
+        decompress(&compressed[..], &mut out)?;
+        prop_assert_eq!(input, out);
+    }
+}
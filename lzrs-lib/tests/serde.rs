@@ -0,0 +1,20 @@
+#![cfg(feature = "serde")]
+
+use lzrs_lib::Token;
+
+#[test]
+fn test_roundtrip() -> serde_json::Result<()> {
+    let tokens = vec![
+        Token::Literal { byte: b'a' },
+        Token::Literal { byte: b'b' },
+        Token::Rep { distance: 1, length: 6 },
+    ];
+
+    let json = serde_json::to_string(&tokens)?;
+    assert_eq!(
+        r#"[{"t":"lit","byte":97},{"t":"lit","byte":98},{"t":"rep","distance":1,"length":6}]"#,
+        json
+    );
+    assert_eq!(tokens, serde_json::from_str::<Vec<Token>>(&json)?);
+    Ok(())
+}
@@ -0,0 +1,102 @@
+use std::io::{Cursor, ErrorKind, Read, Result, Write};
+
+use lzrs_lib::{compress, compress_to_vec, decompress, decompress_to_vec, Config, Reader, Writer};
+
+mod common;
+
+fn config() -> Config {
+    Config::builder().dict_size(0x400).build()
+}
+
+#[test]
+fn test_roundtrip() -> Result<()> {
+    let input = common::cycled(
+        b"It was the best of times, it was the worst of times, it was the age of wisdom, it \
+        was the age of foolishness, it was the epoch of belief, it was the epoch of incredulity, \
+        it was the season of Light, it was the season of Darkness. ",
+        0x1000,
+    );
+
+    let mut compressed = vec![];
+    let read = compress(Cursor::new(&input), &mut compressed, config())?;
+    assert_eq!(input.len() as u64, read);
+    assert!(compressed.len() < input.len() / 4);
+
+    let mut out = vec![];
+    let written = decompress(&compressed[..], &mut out)?;
+    assert_eq!(input.len() as u64, written);
+    assert_eq!(input, out);
+    Ok(())
+}
+
+#[test]
+fn test_decompress_truncated() -> Result<()> {
+    let mut compressed = vec![];
+    compress(Cursor::new(b"banana banana banana"), &mut compressed, config())?;
+
+    // This cuts off the trailer.
+    compressed.pop();
+    let err = decompress(&compressed[..], vec![]).unwrap_err();
+    assert_eq!(ErrorKind::UnexpectedEof, err.kind());
+
+    // After the header and the block kind, a rep as the very first token has no history to copy
+    // from.
+    let compressed = b"LZRS\x01\x01\x80\x08\x01\x01\x00\x04";
+    let err = decompress(&compressed[..], vec![]).unwrap_err();
+    assert_eq!(ErrorKind::InvalidData, err.kind());
+    Ok(())
+}
+
+#[test]
+fn test_to_vec() -> Result<()> {
+    let input = b"banana banana banana";
+    let compressed = compress_to_vec(input, config());
+
+    let mut streamed = vec![];
+    compress(Cursor::new(input), &mut streamed, config())?;
+    assert_eq!(streamed, compressed);
+    assert_eq!(input[..], decompress_to_vec(&compressed)?[..]);
+
+    let err = decompress_to_vec(&compressed[..compressed.len() - 1]).unwrap_err();
+    assert_eq!(ErrorKind::UnexpectedEof, err.kind());
+    Ok(())
+}
+
+#[test]
+fn test_compress_invalid_config() {
+    let config = Config { dict_size: 0, ..config() };
+    let err = compress(Cursor::new(b"abc"), vec![], config).unwrap_err();
+    assert_eq!(ErrorKind::InvalidInput, err.kind());
+}
+
+#[test]
+fn test_preset_dictionary() -> Result<()> {
+    let preset = br#"{"id":0,"name":"","email":"@example.com","active":false}"#;
+    let messages: [&[u8]; 2] = [
+        br#"{"id":17,"name":"thor","email":"thor@example.com","active":true}"#,
+        br#"{"id":42,"name":"lucas","email":"lucas@example.com","active":false}"#,
+    ];
+
+    for message in messages {
+        let mut plain = Writer::new(Vec::new(), config());
+        plain.write_all(message)?;
+        let plain = plain.finish()?;
+
+        let mut primed = Writer::new(Vec::new(), config());
+        primed.set_dictionary(preset);
+        primed.write_all(message)?;
+        let primed = primed.finish()?;
+        assert!(primed.len() < plain.len());
+
+        let mut reader = Reader::new(&primed[..]);
+        reader.set_dictionary(preset);
+        let mut out = vec![];
+        reader.read_to_end(&mut out)?;
+        assert_eq!(message, &out[..]);
+
+        // Without the dictionary, the reps reach back past the start.
+        let err = Reader::new(&primed[..]).read_to_end(&mut vec![]).unwrap_err();
+        assert_eq!(ErrorKind::InvalidData, err.kind());
+    }
+    Ok(())
+}
@@ -0,0 +1,17 @@
+use std::time::Duration;
+
+/// How the debug UI runs.
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// How often the UI is redrawn without any other event. With `None`, it is only redrawn on
+    /// events, such as keys and steps.
+    pub tick_rate: Option<Duration>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            tick_rate: Some(Duration::from_millis(250)),
+        }
+    }
+}
@@ -1,36 +1,64 @@
-use std::{sync::mpsc::{Receiver, self, Sender},io::{self, Result}, thread};
+use std::{sync::mpsc::Sender, thread::{self, JoinHandle}, time::Duration};
 
-use termion::{input::TermRead, event::Key};
+use lzrs_lib::Token;
 use tracing::error;
 
+use crate::ui::{self, Key};
+
 pub enum Event {
     Tick,
     Key(Key),
+    /// The token the compressor produced last.
+    Token(Token),
+    /// A snapshot of the compressor's window.
     LoadDictBuffer {
-        buf: &'static [u8],
+        buf: Vec<u8>,
         head: usize,
     }
 }
 
-pub fn start_event_loop(tx: Sender<Event>) {
+pub fn start_event_loop(tx: Sender<Event>, tick_rate: Option<Duration>) {
     let keys_tx = tx.clone();
     thread::spawn(move || {
-        let stdin = io::stdin();
-        for evt in stdin.keys() {
-            if let Ok(evt) = evt {
-                if let Err(err) = keys_tx.send(Event::Key(evt)) {
-                    error!("{}", err);
-                    return;
-                }
+        for key in ui::keys() {
+            if let Err(err) = keys_tx.send(Event::Key(key)) {
+                error!("{}", err);
+                return;
             }
         }
     });
-    let tick_tx = tx;
-    thread::spawn(move || {
+    start_ticks(tx, tick_rate);
+}
+
+/// Sends a tick every `tick_rate` until the receiver is gone, or returns `None` without starting
+/// a thread if there is no tick rate.
+fn start_ticks(tx: Sender<Event>, tick_rate: Option<Duration>) -> Option<JoinHandle<()>> {
+    let tick_rate = tick_rate?;
+    Some(thread::spawn(move || {
         loop {
-            #[allow(deprecated)]
-            thread::sleep_ms(250);
-            tick_tx.send(Event::Tick).unwrap();
+            thread::sleep(tick_rate);
+            if tx.send(Event::Tick).is_err() {
+                return;
+            }
         }
-    });
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn test_ticks() {
+        let (tx, rx) = mpsc::channel();
+        assert!(start_ticks(tx.clone(), None).is_none());
+
+        let ticks = start_ticks(tx, Some(Duration::from_millis(1))).unwrap();
+        assert!(matches!(rx.recv().unwrap(), Event::Tick));
+
+        // The thread ends once nothing is listening.
+        drop(rx);
+        ticks.join().unwrap();
+    }
 }
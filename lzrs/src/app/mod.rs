@@ -1,28 +1,45 @@
+mod config;
 mod event;
 mod run;
 
+use std::ops::Range;
 use std::sync::mpsc::{Sender, Receiver, self};
 
+pub use config::Config;
 pub use run::run;
 pub use event::{start_event_loop, Event};
 
+use lzrs_lib::Token;
+
 use crate::ui::{UI, UIWriter};
-use crate::trace::Trace;
+use crate::trace::{Step, Trace};
 
 pub struct App<'a> {
+    pub config: Config,
     pub should_quit: bool,
     pub ui: UI<'a>,
     pub trace: Trace<UIWriter>,
-    pub step: Sender<()>,
+    pub step: Sender<Step>,
+    /// Whether the compressor has been told to run without stepping.
+    pub running: bool,
+    /// How many lines the log is scrolled back from the newest one.
+    pub log_scroll: usize,
 
     pub event_rx: Receiver<Event>,
     pub event_tx: Option<Sender<Event>>,
 
-    pub dict: Option<(&'static [u8], usize)>,
+    pub dict: Option<(Vec<u8>, usize)>,
+    /// The bytes of `dict` that were added since it was last loaded.
+    pub dict_recent: Range<usize>,
+    pub token: Option<Token>,
 }
 
 impl App<'_> {
     pub fn new() -> Self {
+        Self::with_config(Config::default())
+    }
+
+    pub fn with_config(config: Config) -> Self {
         let (tx, rx) = mpsc::channel();
 
         let ui = UI::new();
@@ -34,15 +51,26 @@ impl App<'_> {
         let step = trace.take_step_tx().unwrap();
 
         Self {
+            config,
             should_quit: false,
             ui,
             trace,
             step,
+            running: false,
+            log_scroll: 0,
 
             event_rx: rx,
             event_tx: Some(tx),
 
             dict: None,
+            dict_recent: 0..0,
+            token: None,
         }
     }
 }
+
+impl Default for App<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
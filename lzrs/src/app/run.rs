@@ -1,15 +1,18 @@
 use std::io::Result;
-use termion::event::Key;
 use tracing::info;
 
 use super::{start_event_loop, App, event::Event};
-use crate::{ui::{self, draw_loop}, trace};
+use crate::{ui::{self, draw_loop, Key}, trace::{self, Step}};
+
+/// The number of lines the log is scrolled by a page. How far it can be scrolled is only known
+/// once it is drawn.
+const LOG_PAGE: usize = 10;
 
 pub fn run<F>(mut app: App, init: F) -> Result<()>
 where
-    F: FnOnce() -> (),
+    F: FnOnce(),
 {
-    start_event_loop(app.event_tx.take().unwrap());
+    start_event_loop(app.event_tx.take().unwrap(), app.config.tick_rate);
     let mut terminal = ui::start()?;
     trace::start(&mut app.trace);
 
@@ -22,13 +25,28 @@ where
             Event::Tick => (),
             Event::Key(key) => match key {
                 Key::Char('q') => app.should_quit = true,
-                Key::Char(' ') => app.step.send(()).unwrap(),
+                Key::Char(' ') => app.step.send(Step::Next).unwrap(),
+                // Toggles between running to the end and stepping.
+                Key::Char('c') => {
+                    app.running = !app.running;
+                    let step = if app.running { Step::Run } else { Step::Pause };
+                    app.step.send(step).unwrap();
+                }
+                Key::Char('k') | Key::Up => app.log_scroll += 1,
+                Key::Char('j') | Key::Down => app.log_scroll = app.log_scroll.saturating_sub(1),
+                Key::PageUp => app.log_scroll += LOG_PAGE,
+                Key::PageDown => app.log_scroll = app.log_scroll.saturating_sub(LOG_PAGE),
                 _ => (),
             },
             Event::LoadDictBuffer { buf, head } => {
+                app.dict_recent = match &app.dict {
+                    &Some((_, last)) if last <= head => last..head,
+                    _ => 0..0,
+                };
                 app.dict = Some((buf, head));
                 info!("Updated event!");
             },
+            Event::Token(tok) => app.token = Some(tok),
         }
 
         if app.should_quit {
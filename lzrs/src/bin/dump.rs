@@ -0,0 +1,18 @@
+use std::io::{self, Read, Result, Write};
+
+use lzrs::trace::{self, Trace};
+use lzrs_lib::{Config, Writer};
+
+/// Compresses stdin, printing the trace to stdout as plain lines.
+fn main() -> Result<()> {
+    let mut input = vec![];
+    io::stdin().read_to_end(&mut input)?;
+
+    trace::start(&mut Trace::dump(io::stdout));
+
+    let mut comp = Writer::new(io::sink(), Config::builder().dict_size(0x80).build());
+    comp.write_all(&input)?;
+    comp.finish()?;
+
+    Ok(())
+}
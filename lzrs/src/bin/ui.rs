@@ -1,24 +1,41 @@
-use std::thread;
+use std::{env, fs, process, thread};
 use std::io::Result;
 
 use lzrs::app::{run, App};
 use tracing::info;
 
+/// Compressed when no file is given.
+const DEFAULT_INPUT: &[u8] = b"Hey, banana-ass! To banana or not to banana?";
 
+/// Steps through compressing the file given as the first argument, or a short example without one.
 fn main() -> Result<()> {
+    // The file is read up front, since an error can't be shown once the UI has the terminal.
+    let input = match env::args_os().nth(1) {
+        Some(path) => match fs::read(&path) {
+            Ok(input) => input,
+            Err(err) => {
+                eprintln!("Can't read {}: {}", path.to_string_lossy(), err);
+                process::exit(1);
+            }
+        },
+        None => DEFAULT_INPUT.to_vec(),
+    };
+
     let app = App::new();
 
-    run(app, || {
-        thread::spawn(|| {
-            use lzrs_lib::{Writer, Config};
+    run(app, move || {
+        thread::spawn(move || {
+            use lzrs_lib::{util::CountingWriter, Writer, Config};
             use std::io::Write;
 
-            let to: Vec<u8> = Vec::new();
-            let mut comp = Writer::new(to, Config { dict_size: 0x80 });
+            let to = CountingWriter::new(Vec::new());
+            let mut comp = Writer::new(to, Config::builder().dict_size(0x80).build());
 
-            info!("Hello?");
+            info!("Compressing {} bytes.", input.len());
 
-            write!(comp, "Hey, banana-ass! To banana or not to banana?").unwrap();
+            comp.write_all(&input).unwrap();
+            let to = comp.finish().unwrap();
+            info!("Compressed into {} bytes.", to.count());
         });
     })?;
 
@@ -9,11 +9,10 @@ fn main() -> Result<()> {
 
     let to: Vec<u8> = Vec::new();
 
-    let mut comp = Writer::new(to, Config {
-        dict_size: 0x80,
-    });
+    let mut comp = Writer::new(to, Config::builder().dict_size(0x80).build());
 
     write!(comp, "Hey, banana-ass! To banana or not to banana?").unwrap();
+    comp.finish().unwrap();
 
     Ok(())
 }
@@ -0,0 +1,94 @@
+use std::io::Write;
+
+use lzrs_lib::ascii_buf;
+use tracing::{Subscriber, span};
+use tracing_subscriber::{fmt::MakeWriter, registry::LookupSpan, Layer};
+
+use super::ui::{record_dict, TokenVisitor};
+
+/// Prints the tokens and the window as the compressor goes, one line each, instead of sending them
+/// to the UI. Nothing blocks, so the compressor runs to the end.
+pub struct DumpLayer<W> {
+    writer: W,
+}
+
+impl<W> DumpLayer<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+}
+
+impl<S, W> Layer<S> for DumpLayer<W>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+        W: for<'w> MakeWriter<'w> + 'static
+{
+    fn on_event(&self, event: &tracing::Event<'_>, _ctx: tracing_subscriber::layer::Context<'_, S>) {
+        let mut v = TokenVisitor::default();
+        event.record(&mut v);
+
+        if let Some(tok) = v.finish() {
+            // There is nowhere to report the errors to.
+            let _ = writeln!(self.writer.make_writer(), "token {}", tok);
+        }
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: tracing_subscriber::layer::Context<'_, S>) {
+        let span = ctx.span(id).unwrap();
+        if span.fields().field("dict.window").is_some() {
+            if let Some((buf, head)) = record_dict(&span, values) {
+                let _ = writeln!(
+                    self.writer.make_writer(),
+                    "window head={} len={} \"{}\"",
+                    head,
+                    buf.len(),
+                    ascii_buf(&buf)
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::UIWriter;
+    use lzrs_lib::{Config, Writer};
+    use tracing_subscriber::prelude::*;
+
+    fn dump(input: &[u8]) -> String {
+        let out = UIWriter::new();
+        let sub = tracing_subscriber::registry().with(DumpLayer::new(out.clone()));
+
+        tracing::subscriber::with_default(sub, || {
+            let mut comp = Writer::new(Vec::new(), Config::builder().dict_size(0x80).build());
+            comp.write_all(input).unwrap();
+            comp.write_all(input).unwrap();
+            comp.finish().unwrap();
+        });
+
+        let buf = out.buf.lock().unwrap();
+        String::from_utf8(buf.clone()).unwrap()
+    }
+
+    #[test]
+    fn test_dump() {
+        let expected = [
+            // Each write, then the window each token is looked for in.
+            r#"window head=0 len=0 """#,
+            r#"window head=0 len=0 """#,
+            r#"window head=0 len=0 """#,
+            "token <LIT 'a'>",
+            r#"window head=1 len=1 "a""#,
+            "token <LIT 'b'>",
+            r#"window head=2 len=2 "ab""#,
+            "token <LIT 'c'>",
+            r#"window head=3 len=3 "abc""#,
+            "token <REP 2, 3>",
+            "",
+        ]
+        .join("\n");
+        assert_eq!(expected, dump(b"abc"));
+        assert_eq!(dump(b"abc"), dump(b"abc"));
+    }
+}
@@ -2,17 +2,21 @@ use std::sync::mpsc::Sender;
 use tracing_subscriber::fmt::MakeWriter;
 use crate::app::Event;
 
-use self::{ui::UILayer, step::StepLayer};
+use self::{ui::UILayer, step::StepLayer, dump::DumpLayer};
 
+mod dump;
 mod step;
 mod ui;
 mod start;
 
 pub use start::start;
+pub use step::Step;
 
 pub struct Trace<W> {
     ui_layer: Option<UILayer>,
     step_layer: Option<StepLayer>,
+    /// Replaces the other layers, printing the trace to `writer` instead of logging to it.
+    dump_layer: Option<DumpLayer<W>>,
     writer: W,
 }
 
@@ -24,11 +28,26 @@ impl<W> Trace<W>
         Self {
             ui_layer: Some(UILayer::new()),
             step_layer: Some(StepLayer::new()),
+            dump_layer: None,
             writer,
         }
     }
 
-    pub fn take_step_tx(&mut self) -> Option<Sender<()>> {
+    /// A trace that prints each token and the window as plain lines to `writer`, without the UI
+    /// or stepping.
+    pub fn dump(writer: W) -> Self
+        where
+            W: Clone
+    {
+        Self {
+            ui_layer: None,
+            step_layer: None,
+            dump_layer: Some(DumpLayer::new(writer.clone())),
+            writer,
+        }
+    }
+
+    pub fn take_step_tx(&mut self) -> Option<Sender<Step>> {
         if let Some(step_layer) = &mut self.step_layer {
             step_layer.tx.lock().unwrap().take() 
         } else {
@@ -4,15 +4,22 @@ use tracing_subscriber::prelude::*;
 
 use super::Trace;
 
+// The targets are matched on to toggle the commented out filters.
+#[allow(clippy::match_single_binding)]
 pub fn start<W>(trace: &mut Trace<W>)
     where
         W: for<'w> MakeWriter<'w> + 'static + Send + Sync + Clone
 { 
+    if let Some(dump_layer) = trace.dump_layer.take() {
+        tracing_subscriber::registry().with(dump_layer).init();
+        return;
+    }
+
     let fmt_layer = tracing_subscriber::fmt::layer()
         .with_ansi(true)
         .with_writer(trace.writer.clone());
         
-    let sub = tracing_subscriber::registry()
+    tracing_subscriber::registry()
         .with(
             fmt_layer
             .with_filter(filter_fn(|meta| {
@@ -36,6 +43,4 @@ pub fn start<W>(trace: &mut Trace<W>)
             }))
         )
         .init();
-
-    sub
 }
@@ -1,11 +1,28 @@
-use std::sync::{mpsc::{Receiver, Sender, self}, Mutex};
+use std::sync::{mpsc::{Receiver, Sender, TryRecvError, self}, Mutex};
 
 use tracing::{Subscriber, span};
 use tracing_subscriber::{registry::LookupSpan, Layer};
 
+/// What the `StepLayer` is told to do over its channel.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Step {
+    /// Enter the next span. Ignored while running.
+    Next,
+    /// Stop blocking on spans.
+    Run,
+    /// Go back to blocking on each span.
+    Pause,
+}
+
+/// Blocks the thread on entering a span until it is told to step, unless it is running.
 pub struct StepLayer {
-    pub(super) tx: Mutex<Option<Sender<()>>>,
-    rx: Mutex<Receiver<()>>,
+    pub(super) tx: Mutex<Option<Sender<Step>>>,
+    state: Mutex<State>,
+}
+
+struct State {
+    rx: Receiver<Step>,
+    running: bool,
 }
 
 impl StepLayer {
@@ -13,7 +30,34 @@ impl StepLayer {
         let (tx, rx) = mpsc::channel();
         Self {
             tx: Mutex::new(Some(tx)),
-            rx: Mutex::new(rx)
+            state: Mutex::new(State { rx, running: false }),
+        }
+    }
+
+    /// Waits for the next step. While running, this only picks up the steps that were sent so
+    /// far, in case it is paused. If the sender is gone, nothing is waited for.
+    fn wait(&self) {
+        let mut state = self.state.lock().unwrap();
+
+        loop {
+            let step = if state.running {
+                match state.rx.try_recv() {
+                    Ok(step) => step,
+                    Err(TryRecvError::Empty | TryRecvError::Disconnected) => return,
+                }
+            } else {
+                match state.rx.recv() {
+                    Ok(step) => step,
+                    Err(_) => return,
+                }
+            };
+
+            match step {
+                Step::Next if !state.running => return,
+                Step::Next => (),
+                Step::Run => state.running = true,
+                Step::Pause => state.running = false,
+            }
         }
     }
 }
@@ -24,7 +68,44 @@ impl<S> Layer<S> for StepLayer
 {
     fn on_enter(&self, id: &span::Id, ctx: tracing_subscriber::layer::Context<'_, S>) {
         if let Some(_span) = ctx.span(id) {
-            self.rx.lock().unwrap().recv().unwrap();
+            self.wait();
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{thread, time::Duration};
+
+    #[test]
+    fn test_steps() {
+        let layer = StepLayer::new();
+        let tx = layer.tx.lock().unwrap().take().unwrap();
+
+        tx.send(Step::Next).unwrap();
+        layer.wait();
+
+        // While running, nothing is waited for and steps are dropped.
+        tx.send(Step::Run).unwrap();
+        layer.wait();
+        tx.send(Step::Next).unwrap();
+        layer.wait();
+        layer.wait();
+        assert!(layer.state.lock().unwrap().running);
+
+        // Pausing blocks right away, until the next step.
+        tx.send(Step::Pause).unwrap();
+        let stepper = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            tx.send(Step::Next).unwrap();
+            tx
+        });
+        layer.wait();
+        assert!(!layer.state.lock().unwrap().running);
+
+        // Without a sender there is nothing to wait for.
+        drop(stepper.join().unwrap());
+        layer.wait();
+    }
+}
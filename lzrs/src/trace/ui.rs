@@ -1,7 +1,8 @@
-use std::sync::{Mutex, mpsc::{Receiver, Sender}};
+use std::sync::{Mutex, mpsc::Sender};
 
-use tracing::{Subscriber, span, info, subscriber::Interest, Metadata, debug, field::{Visit, Field}};
-use tracing_subscriber::{registry::LookupSpan, Layer, prelude::*};
+use lzrs_lib::{ascii_buf, Token};
+use tracing::{Subscriber, span, info, field::{Visit, Field}};
+use tracing_subscriber::{registry::{LookupSpan, SpanRef}, Layer};
 
 use crate::app::Event;
 
@@ -27,95 +28,226 @@ impl<S> Layer<S> for UILayer
     fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: tracing_subscriber::layer::Context<'_, S>) {
         info!("New span");
         let span = ctx.span(id).unwrap();        
-        if let Some(field) = span.fields().field("dict.ptr") {
+        if let Some(field) = span.fields().field(DictVisitor::DICT_WINDOW) {
             info!(field.name = field.name(), span.name = span.name(), "Found a dict field!");
 
             let mut v: DictVisitor = Default::default();
             attrs.record(&mut v);
             
-            info!(dict.ptr = v.ptr.unwrap() as usize, dict.len = v.len, dict.head = v.head, "Got fields!");
+            info!(dict.len = v.window.map(|window| window.len()), dict.head = v.head, "Got fields!");
         }
     }
 
-    fn on_enter(&self, id: &span::Id, ctx: tracing_subscriber::layer::Context<'_, S>) {
+    fn on_enter(&self, _id: &span::Id, _ctx: tracing_subscriber::layer::Context<'_, S>) {
         info!("Enter");
     }
 
-    fn on_event(&self, _event: &tracing::Event<'_>, _ctx: tracing_subscriber::layer::Context<'_, S>) {
+    fn on_event(&self, event: &tracing::Event<'_>, _ctx: tracing_subscriber::layer::Context<'_, S>) {
         info!("Event");
+
+        let mut v = TokenVisitor::default();
+        event.record(&mut v);
+
+        if let Some(tok) = v.finish() {
+            if let Some(tx) = &mut *self.tx.lock().unwrap() {
+                tx.send(Event::Token(tok)).unwrap();
+            }
+        }
     }
 
     fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: tracing_subscriber::layer::Context<'_, S>) {
         let span = ctx.span(id).unwrap();        
-        if let Some(field) = span.fields().field("dict.ptr") {
+        if let Some(field) = span.fields().field(DictVisitor::DICT_WINDOW) {
             info!(field.name = field.name(), span.name = span.name(), "Found a dict field!");
 
-            let mut v: DictVisitor = if let Some(old) = span.extensions().get() {
-                *old
-            } else {
-                DictVisitor::default()
-            };
-
-            values.record(&mut v);
-
-            if v.is_done() {
-                let v = v.finish();
-                let lower = (v.1 as isize - 5).max(0) as usize;
-                let upper = (v.1 + 5).min(v.0.len());
-                let b = &(v.0)[lower..upper];
-                let s = unsafe { std::str::from_utf8_unchecked(b) };
-                info!(dict.ptr = s, dict.len = v.0.len(), dict.head = v.1, "Finished dictionary buffer!");
+            if let Some((buf, head)) = record_dict(&span, values) {
+                let lower = head.saturating_sub(5);
+                let upper = (head + 5).min(buf.len());
+                let s = ascii_buf(&buf[lower..upper]);
+                info!(dict.window = s.as_str(), dict.len = buf.len(), dict.head = head, "Finished dictionary buffer!");
 
                 if let Some(tx) = &mut *self.tx.lock().unwrap() {
-                    tx.send(Event::LoadDictBuffer {
-                        buf: v.0,
-                        head: v.1,
-                    }).unwrap();
+                    tx.send(Event::LoadDictBuffer { buf, head }).unwrap();
                 }
-
-                span.extensions_mut().replace(v);
-            } else {
-                span.extensions_mut().replace(v);
             }
         }
     }
 }
 
-#[derive(Copy, Clone, Default)]
+/// Records the dictionary fields of a span into its `DictVisitor`, since they are recorded one at
+/// a time. Returns the window and its head once all of them have been.
+pub(super) fn record_dict<S>(span: &SpanRef<'_, S>, values: &span::Record<'_>) -> Option<(Vec<u8>, usize)>
+    where
+        S: for<'a> LookupSpan<'a>
+{
+    let mut extensions = span.extensions_mut();
+    if extensions.get_mut::<DictVisitor>().is_none() {
+        extensions.insert(DictVisitor::default());
+    }
+    let v = extensions.get_mut::<DictVisitor>().unwrap();
+    values.record(v);
+
+    if v.is_done() {
+        std::mem::take(v).finish()
+    } else {
+        None
+    }
+}
+
+#[derive(Clone, Default)]
 struct DictVisitor {
-    pub ptr: Option<usize>,
-    pub len: Option<usize>,
+    pub window: Option<Vec<u8>>,
     pub head: Option<usize>,
 }
 
 impl DictVisitor {
     pub fn is_done(&self) -> bool {
-        self.ptr.is_some() && self.len.is_some() && self.head.is_some()
+        self.window.is_some() && self.head.is_some()
     }
 
-    pub fn finish(mut self) -> (&'static [u8], usize) {
-        let ptr: *const u8 = unsafe { std::mem::transmute(self.ptr.unwrap()) };
-        let buf: &'static [u8] = unsafe { std::slice::from_raw_parts(ptr, self.len.unwrap()) };
-        let head: usize = self.head.unwrap();
-        (buf, head)
+    /// Returns a snapshot of the window, along with its head.
+    pub fn finish(self) -> Option<(Vec<u8>, usize)> {
+        Some((self.window?, self.head?))
     }
 }
 
 impl DictVisitor {
-    const DICT_PTR: &'static str = "dict.ptr";
-    const DICT_LEN: &'static str = "dict.len";
+    const DICT_WINDOW: &'static str = "dict.window";
     const DICT_HEAD: &'static str = "dict.head";
 }
 
 impl Visit for DictVisitor {
-    fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {}
+    fn record_debug(&mut self, _field: &tracing::field::Field, _value: &dyn std::fmt::Debug) {}
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == DictVisitor::DICT_WINDOW {
+            // Each byte of the window is recorded as the char with the same value.
+            self.window = Some(value.chars().map(|c| c as u8).collect());
+        }
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        if field.name() == DictVisitor::DICT_HEAD {
+            self.head = Some(value as usize);
+        }
+    }
+}
+
+#[derive(Copy, Clone, Default)]
+pub(super) struct TokenVisitor {
+    pub byte: Option<u8>,
+    pub distance: Option<usize>,
+    pub length: Option<usize>,
+}
+
+impl TokenVisitor {
+    const TOKEN_BYTE: &'static str = "token.byte";
+    const TOKEN_DISTANCE: &'static str = "token.distance";
+    const TOKEN_LENGTH: &'static str = "token.length";
+
+    /// Returns the token, or `None` if the event didn't describe one.
+    pub fn finish(self) -> Option<Token> {
+        match self {
+            TokenVisitor { byte: Some(byte), .. } => Some(Token::Literal { byte }),
+            TokenVisitor { distance: Some(distance), length: Some(length), .. } => {
+                Some(Token::Rep { distance, length })
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Visit for TokenVisitor {
+    fn record_debug(&mut self, _field: &tracing::field::Field, _value: &dyn std::fmt::Debug) {}
 
     fn record_u64(&mut self, field: &Field, value: u64) {
         match field.name() {
-            DictVisitor::DICT_PTR => self.ptr = Some(value as usize),
-            DictVisitor::DICT_LEN => self.len = Some(value as usize),
-            DictVisitor::DICT_HEAD => self.head = Some(value as usize),
+            TokenVisitor::TOKEN_BYTE => self.byte = Some(value as u8),
+            TokenVisitor::TOKEN_DISTANCE => self.distance = Some(value as usize),
+            TokenVisitor::TOKEN_LENGTH => self.length = Some(value as usize),
             _ => (),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lzrs_lib::{Config, Writer};
+    use std::io::Write;
+    use std::sync::mpsc;
+    use tracing_subscriber::{filter::filter_fn, prelude::*};
+
+    /// The events the layer sends while `input` is compressed.
+    fn events(input: &[u8]) -> Vec<Event> {
+        let (tx, rx) = mpsc::channel();
+        let layer = UILayer::new();
+        *layer.tx.lock().unwrap() = Some(tx);
+
+        // The layer logs itself, which it mustn't see, just like in `trace::start`.
+        let sub = tracing_subscriber::registry()
+            .with(layer.with_filter(filter_fn(|meta| !meta.target().starts_with("lzrs::"))));
+
+        tracing::subscriber::with_default(sub, || {
+            let mut comp = Writer::new(Vec::new(), Config::builder().dict_size(0x80).build());
+            comp.write_all(input).unwrap();
+            comp.finish().unwrap();
+        });
+
+        rx.try_iter().collect()
+    }
+
+    #[test]
+    fn test_token_event() {
+        let tokens: Vec<Token> = events(b"abcabc")
+            .into_iter()
+            .filter_map(|event| match event {
+                Event::Token(tok) => Some(tok),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                Token::Literal { byte: b'a' },
+                Token::Literal { byte: b'b' },
+                Token::Literal { byte: b'c' },
+                Token::Rep { distance: 2, length: 3 },
+            ],
+            tokens
+        );
+    }
+
+    #[test]
+    fn test_dict_fields() {
+        // The snapshots outlive the compressor.
+        let dicts: Vec<(Vec<u8>, usize)> = events(b"abcabc")
+            .into_iter()
+            .filter_map(|event| match event {
+                Event::LoadDictBuffer { buf, head } => Some((buf, head)),
+                _ => None,
+            })
+            .collect();
+
+        // One for the write, then one per token.
+        let expected: Vec<(Vec<u8>, usize)> = vec![
+            (b"".to_vec(), 0),
+            (b"".to_vec(), 0),
+            (b"a".to_vec(), 1),
+            (b"ab".to_vec(), 2),
+            (b"abc".to_vec(), 3),
+        ];
+        assert_eq!(expected, dicts);
+
+        // Bytes that aren't ASCII survive being recorded as chars.
+        let input: Vec<u8> = (0x80..=0xff).collect();
+        let last = events(&input)
+            .into_iter()
+            .filter_map(|event| match event {
+                Event::LoadDictBuffer { buf, .. } => Some(buf),
+                _ => None,
+            })
+            .next_back()
+            .unwrap();
+        assert_eq!(input[..0x7f], last[..]);
+    }
+}
@@ -0,0 +1,66 @@
+use std::io::{self, Result, Stdout, Write};
+use crossterm::{cursor, event::{self, Event, KeyCode}, execute, terminal};
+use tui::backend::CrosstermBackend;
+
+use super::Key;
+
+pub type Backend = CrosstermBackend<Screen>;
+
+/// Stdout in raw mode and on the alternate screen, which are both undone when it is dropped,
+/// like termion does.
+pub struct Screen(Stdout);
+
+impl Write for Screen {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.0.flush()
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        restore();
+    }
+}
+
+/// Switches the terminal to raw mode and the alternate screen, which are both undone when the
+/// backend is dropped.
+pub fn backend() -> Result<Backend> {
+    terminal::enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, terminal::EnterAlternateScreen)?;
+    Ok(CrosstermBackend::new(Screen(stdout)))
+}
+
+/// Leaves the alternate screen and raw mode, and shows the cursor again. Doing this more than
+/// once is harmless.
+pub fn restore() {
+    // There is nothing left to report the errors to.
+    let _ = execute!(io::stdout(), terminal::LeaveAlternateScreen, cursor::Show);
+    let _ = terminal::disable_raw_mode();
+}
+
+/// Blocks on the keys pressed, until reading the terminal fails.
+pub fn keys() -> impl Iterator<Item = Key> {
+    std::iter::from_fn(|| loop {
+        match event::read() {
+            Ok(Event::Key(key)) => return Some(key.code),
+            Ok(_) => continue,
+            Err(_) => return None,
+        }
+    })
+    .map(|code| match code {
+        KeyCode::Char(c) => Key::Char(c),
+        KeyCode::Up => Key::Up,
+        KeyCode::Down => Key::Down,
+        KeyCode::Left => Key::Left,
+        KeyCode::Right => Key::Right,
+        KeyCode::PageUp => Key::PageUp,
+        KeyCode::PageDown => Key::PageDown,
+        KeyCode::Esc => Key::Esc,
+        _ => Key::Other,
+    })
+}
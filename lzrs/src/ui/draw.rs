@@ -1,8 +1,11 @@
 use ansi_to_tui::ansi_to_text;
-use std::io::Write;
+use lzrs_lib::{ascii_buf, Token};
+use std::{io::Write, ops::Range};
 use tui::{
     backend::Backend,
     layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans, Text},
     widgets::{Block, Borders, Paragraph},
     Frame,
 };
@@ -16,8 +19,17 @@ pub fn draw<B: Backend>(f: &mut Frame<B>, app: &mut App) {
         .constraints([Constraint::Percentage(60), Constraint::Percentage(40)].as_ref())
         .split(f.size());
 
+    let top_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(80), Constraint::Percentage(20)].as_ref())
+        .split(chunks[0]);
+    let dict_chunk = top_chunks[0];
+    let token_chunk = top_chunks[1];
     let log_chunk = chunks[1];
 
+    f.render_widget(dict_widget(app.dict.as_ref().map(|(buf, head)| (&buf[..], *head)), app.dict_recent.clone()), dict_chunk);
+    f.render_widget(token_widget(app.token), token_chunk);
+
     let text = {
         app.ui.log_buffer.flush().unwrap();
         let buf: Vec<u8> = app.ui.log_buffer.buf.lock().unwrap().drain(..).collect();
@@ -25,15 +37,55 @@ pub fn draw<B: Backend>(f: &mut Frame<B>, app: &mut App) {
     };
     app.ui.log.extend(text);
 
-    let chunk_height = (log_chunk.height as usize) - 2;
+    // The whole log is kept, and only the lines in view are rendered. The scroll offset counts
+    // lines back from the newest one.
+    let chunk_height = (log_chunk.height as usize).saturating_sub(2);
     let lines = app.ui.log.lines.len();
-    if lines > chunk_height {
-        app.ui.log.lines = app.ui.log.lines[(lines - chunk_height)..].to_vec();
-    }
+    app.log_scroll = app.log_scroll.min(lines.saturating_sub(chunk_height));
+    let end = lines - app.log_scroll;
+    let start = end.saturating_sub(chunk_height);
 
-    let log_widget = Paragraph::new(app.ui.log.clone())
+    let log_widget = Paragraph::new(Text::from(app.ui.log.lines[start..end].to_vec()))
         .block(Block::default().title("Log").borders(Borders::ALL))
         .wrap(tui::widgets::Wrap { trim: false });
 
     f.render_widget(log_widget, log_chunk);
 }
+
+/// Shows the dictionary with the bytes in `recent` colored and the head highlighted.
+fn dict_widget(dict: Option<(&[u8], usize)>, recent: Range<usize>) -> Paragraph<'static> {
+    let block = Block::default().title("Dictionary").borders(Borders::ALL);
+
+    let (buf, head) = match dict {
+        Some((buf, head)) => (buf, head.min(buf.len())),
+        None => return Paragraph::new("Waiting for the dictionary...").block(block),
+    };
+    let recent = recent.start.min(head)..head;
+
+    // Until the dictionary fills up, the head is past the last byte.
+    let (at_head, rest) = match buf.get(head) {
+        Some(_) => (ascii_buf(&buf[head..=head]), &buf[head + 1..]),
+        None => (" ".to_string(), &[][..]),
+    };
+
+    let spans = Spans::from(vec![
+        Span::raw(ascii_buf(&buf[..recent.start])),
+        Span::styled(ascii_buf(&buf[recent]), Style::default().fg(Color::Yellow)),
+        Span::styled(at_head, Style::default().add_modifier(Modifier::REVERSED)),
+        Span::raw(ascii_buf(rest)),
+    ]);
+
+    Paragraph::new(spans)
+        .block(block)
+        .wrap(tui::widgets::Wrap { trim: false })
+}
+
+/// Shows the token the compressor produced last.
+fn token_widget(token: Option<Token>) -> Paragraph<'static> {
+    let text = match token {
+        Some(tok) => Span::styled(tok.to_string(), Style::default().fg(Color::Yellow)),
+        None => Span::raw("No token yet."),
+    };
+
+    Paragraph::new(text).block(Block::default().title("Token").borders(Borders::ALL))
+}
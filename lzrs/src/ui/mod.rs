@@ -4,9 +4,34 @@ mod draw;
 mod writer;
 mod start;
 
+#[cfg(not(any(feature = "termion", feature = "crossterm")))]
+compile_error!("The debug UI needs either the `termion` or the `crossterm` feature.");
+
+#[cfg(feature = "crossterm")]
+#[path = "crossterm.rs"]
+mod backend;
+#[cfg(all(feature = "termion", not(feature = "crossterm")))]
+#[path = "termion.rs"]
+mod backend;
+
 pub use writer::UIWriter;
 pub use draw::draw;
 pub use start::{start, draw_loop};
+pub use backend::keys;
+
+/// A key pressed, whichever backend read it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Up,
+    Down,
+    Left,
+    Right,
+    PageUp,
+    PageDown,
+    Esc,
+    Other,
+}
 
 pub struct UI<'a> {
     pub log_buffer: UIWriter,
@@ -25,3 +50,9 @@ impl<'a> UI<'a> {
         self.log_buffer.clone()
     }
 }
+
+impl Default for UI<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,17 +1,24 @@
-use std::io::{self, Result};
-use tui::{backend::{TermionBackend, Backend}, Terminal};
-use termion::{screen::AlternateScreen, raw::IntoRawMode};
+use std::io::Result;
+use std::{panic, process};
+use tui::{backend::Backend, Terminal};
 
 use crate::app::App;
-use super::draw;
+use super::{backend, draw};
 
+/// Takes over the terminal for the UI. If anything panics while the UI has it, on any thread, the
+/// terminal is given back before the panic is reported, and the process exits, since the UI can't
+/// go on without it.
 pub fn start() -> Result<Terminal<impl Backend>> {
-    let stdout = io::stdout().into_raw_mode()?;
-    let stdout = AlternateScreen::from(stdout);
-    let backend = TermionBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+    let mut terminal = Terminal::new(backend::backend()?)?;
+
+    let report = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        backend::restore();
+        report(info);
+        process::exit(101);
+    }));
+
     terminal.clear()?;
-    
     Ok(terminal)
 }
 
@@ -0,0 +1,68 @@
+use std::io::{self, Result, Stdout, Write};
+use std::sync::Mutex;
+use termion::{cursor, event, input::TermRead, raw::{IntoRawMode, RawTerminal}, screen};
+use tui::backend::TermionBackend;
+
+use super::Key;
+
+pub type Backend = TermionBackend<Screen>;
+
+/// The terminal as it was before raw mode, which is restored when this is dropped. It is kept
+/// apart from the backend so that [`restore`] can get to it from a panic hook.
+static RAW: Mutex<Option<RawTerminal<Stdout>>> = Mutex::new(None);
+
+/// Stdout in raw mode and on the alternate screen, which are both undone when it is dropped.
+pub struct Screen(Stdout);
+
+impl Write for Screen {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.0.flush()
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        restore();
+    }
+}
+
+/// Switches the terminal to raw mode and the alternate screen, which are both undone when the
+/// backend is dropped.
+pub fn backend() -> Result<Backend> {
+    let raw = io::stdout().into_raw_mode()?;
+    *RAW.lock().unwrap_or_else(|err| err.into_inner()) = Some(raw);
+
+    let mut stdout = io::stdout();
+    write!(stdout, "{}", screen::ToAlternateScreen)?;
+    stdout.flush()?;
+    Ok(TermionBackend::new(Screen(stdout)))
+}
+
+/// Leaves the alternate screen and raw mode, and shows the cursor again. Doing this more than
+/// once is harmless.
+pub fn restore() {
+    let mut stdout = io::stdout();
+    // There is nothing left to report the errors to.
+    let _ = write!(stdout, "{}{}", screen::ToMainScreen, cursor::Show);
+    let _ = stdout.flush();
+    RAW.lock().unwrap_or_else(|err| err.into_inner()).take();
+}
+
+/// Blocks on the keys pressed, until stdin is closed.
+pub fn keys() -> impl Iterator<Item = Key> {
+    io::stdin().keys().filter_map(|key| key.ok()).map(|key| match key {
+        event::Key::Char(c) => Key::Char(c),
+        event::Key::Up => Key::Up,
+        event::Key::Down => Key::Down,
+        event::Key::Left => Key::Left,
+        event::Key::Right => Key::Right,
+        event::Key::PageUp => Key::PageUp,
+        event::Key::PageDown => Key::PageDown,
+        event::Key::Esc => Key::Esc,
+        _ => Key::Other,
+    })
+}
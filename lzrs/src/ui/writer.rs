@@ -1,34 +1,59 @@
-use std::{io::{Write, Read}, sync::{Mutex, Arc}};
+use std::{fs::File, io::{self, Write, Read}, path::Path, sync::{Mutex, Arc}};
 
 use tracing_subscriber::fmt::MakeWriter;
 
 #[derive(Clone)]
 pub struct UIWriter {
     pub buf: Arc<Mutex<Vec<u8>>>,
+    /// A file that everything written is also written to, so that the log outlives the UI.
+    file: Option<Arc<Mutex<File>>>,
 }
 
 impl UIWriter {
     pub fn new() -> Self {
         Self {
             buf: Arc::new(Mutex::new(vec![])),
+            file: None,
         }
     }
 
+    /// Creates a writer that also writes everything to the file at `path`, which is truncated.
+    pub fn with_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(Self {
+            file: Some(Arc::new(Mutex::new(File::create(path)?))),
+            ..Self::new()
+        })
+    }
+
     pub fn available(&self) -> usize {
         self.buf.lock().unwrap().len() 
     }
 }
 
+impl Default for UIWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Write for UIWriter {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
         //println!("{}", String::from_utf8(buf.to_vec()).unwrap());
         //println!("Writing {} bytes into", buf.len());
-        self.buf.lock().unwrap().write(buf)
+        let n = self.buf.lock().unwrap().write(buf)?;
+        if let Some(file) = &self.file {
+            file.lock().unwrap().write_all(&buf[..n])?;
+        }
+        Ok(n)
     }
 
     fn flush(&mut self) -> std::io::Result<()> {
         //println!("Flushing");
-        self.buf.lock().unwrap().flush()
+        self.buf.lock().unwrap().flush()?;
+        if let Some(file) = &self.file {
+            file.lock().unwrap().flush()?;
+        }
+        Ok(())
     }
 }
 
@@ -45,3 +70,24 @@ impl MakeWriter<'_> for UIWriter {
         self.clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_with_file() -> io::Result<()> {
+        let path = std::env::temp_dir().join(format!("lzrs-ui-log-{}", std::process::id()));
+        let mut writer = UIWriter::with_file(&path)?;
+
+        // Clones share the file, like they share the buffer.
+        writer.write_all(b"Hello, ")?;
+        writer.clone().write_all(b"log!")?;
+        writer.flush()?;
+
+        assert_eq!(b"Hello, log!"[..], writer.buf.lock().unwrap()[..]);
+        assert_eq!(b"Hello, log!"[..], fs::read(&path)?[..]);
+        fs::remove_file(&path)
+    }
+}
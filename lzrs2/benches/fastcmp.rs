@@ -1,4 +1,4 @@
-use criterion::{criterion_group, criterion_main, Throughput, Criterion, BenchmarkId, black_box};
+use criterion::{criterion_group, criterion_main, Throughput, Criterion, black_box};
 use lzrs2::buffer::*;
 use rand::{Fill, thread_rng};
 
@@ -21,7 +21,7 @@ pub fn fastcmp_u8(c: &mut Criterion) {
 
     group.bench_function("fastcmp_u8", |b| {
         b.iter(|| {
-            let needle: &[u8] = black_box(&needle);
+            let needle: &[u8] = black_box(needle);
             assert_eq!(LEN, haystack.match_length(black_box(needle)))
         });
     });
@@ -29,7 +29,7 @@ pub fn fastcmp_u8(c: &mut Criterion) {
     group.bench_function("naive", |b| {
         b.iter(|| {
             let mut len = 0;
-            let needle: &[u8] = black_box(&needle);
+            let needle: &[u8] = black_box(needle);
             let max_len = std::cmp::min(needle.len(), haystack.len());
             while len < max_len && haystack[len] == needle[len] {
                 len += 1
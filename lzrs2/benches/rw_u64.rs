@@ -0,0 +1,41 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use lzrs2::buffer::raw::*;
+use rand::{thread_rng, Fill};
+
+const LEN: usize = 4096;
+
+pub fn rw_u64(c: &mut Criterion) {
+    let mut rng = thread_rng();
+
+    let src: &mut [u8] = &mut vec![0; LEN];
+    src.try_fill(&mut rng).unwrap();
+    let dst: &mut [u8] = &mut vec![0; LEN];
+
+    let mut group = c.benchmark_group("rw_u64");
+    group.throughput(Throughput::Bytes(LEN as u64));
+
+    group.bench_function("checked", |b| {
+        b.iter(|| {
+            let src: &[u8] = black_box(src);
+            for i in (0..LEN).step_by(8) {
+                write_u64(dst, i, read_u64(src, i));
+            }
+            black_box(&dst);
+        });
+    });
+
+    group.bench_function("unchecked", |b| {
+        b.iter(|| {
+            let src: &[u8] = black_box(src);
+            for i in (0..LEN).step_by(8) {
+                unsafe { write_u64_unchecked(dst, i, read_u64_unchecked(src, i)) };
+            }
+            black_box(&dst);
+        });
+    });
+
+    group.finish();
+}
+
+criterion_group!(rw, rw_u64);
+criterion_main!(rw);
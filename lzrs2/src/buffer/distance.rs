@@ -0,0 +1,71 @@
+use core::fmt;
+
+/// A distance back from the newest byte in a buffer, where a distance of 0 is the last byte
+/// written, the same as the distance of a `Rep` token.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Distance(pub(crate) usize);
+
+impl Distance {
+    #[inline(always)]
+    pub const fn new(distance: usize) -> Self {
+        Self(distance)
+    }
+
+    #[inline(always)]
+    pub const fn get(&self) -> usize {
+        self.0
+    }
+
+    /// Moves the distance further back, as happens to a fixed position when `n` more bytes are
+    /// written. Returns `None` on overflow.
+    #[inline]
+    pub fn checked_add(self, n: usize) -> Option<Self> {
+        self.0.checked_add(n).map(Self)
+    }
+
+    /// Moves the distance `n` bytes closer to the head, stopping at the last byte written.
+    #[inline]
+    pub fn saturating_sub(self, n: usize) -> Self {
+        Self(self.0.saturating_sub(n))
+    }
+}
+
+impl From<usize> for Distance {
+    #[inline(always)]
+    fn from(distance: usize) -> Self {
+        Self(distance)
+    }
+}
+
+impl From<Distance> for usize {
+    #[inline(always)]
+    fn from(distance: Distance) -> Self {
+        distance.0
+    }
+}
+
+impl fmt::Display for Distance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "-{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn test_distance() {
+        let d = Distance::new(3);
+        assert_eq!(3, d.get());
+        assert_eq!(d, Distance::from(3));
+        assert_eq!(3usize, d.into());
+        assert_eq!("-3", d.to_string());
+
+        assert_eq!(Some(Distance::new(5)), d.checked_add(2));
+        assert_eq!(None, d.checked_add(usize::MAX));
+        assert_eq!(Distance::new(1), d.saturating_sub(2));
+        assert_eq!(Distance::new(0), d.saturating_sub(4));
+    }
+}
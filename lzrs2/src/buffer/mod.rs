@@ -1,32 +1,160 @@
 //! This module provides buffer structures to be used for a variety of purposes, such as the back
 //! end of a dictionary.
 
-use std::ops;
+use core::ops;
 
+mod distance;
 pub mod raw;
 pub mod ringbuf;
+pub mod u8;
+pub mod vecbuf;
+
+pub use self::{
+    distance::Distance,
+    u8::{FastCmp, ReadU64, WriteU64},
+};
 
 pub mod prelude {
-    pub use super::{raw::*, ringbuf::RingBuf, Buffer};
+    pub use super::{
+        raw::*,
+        ringbuf::RingBuf,
+        u8::{FastCmp, ReadU64, WriteU64},
+        vecbuf::VecBuf,
+        Buffer, Distance,
+    };
 }
 
-pub trait Buffer: ops::Index<usize> {
+/// A byte buffer holding a window of the data written into it. Bytes are indexed virtually: the
+/// index of a byte is the number of bytes written before it, so indices never shift as the window
+/// moves forward.
+pub trait Buffer: ops::Index<usize, Output = u8> {
     fn with_capacity(capacity: usize) -> Self;
 
-    /*
+    /// The number of valid bytes in the buffer.
+    fn len(&self) -> usize;
+
+    #[inline(always)]
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Gets the byte at the virtual `index`, or `None` if it has not been written yet or is no
+    /// longer in the buffer.
     fn get(&self, index: usize) -> Option<&u8>;
+
+    /// Gets a pointer to the byte at the virtual `index` without checking that it is valid.
+    ///
+    /// # Safety
+    ///
+    /// `index` must be one of the last [`Buffer::len`] bytes written.
     unsafe fn get_unchecked(&self, index: usize) -> *const u8;
-    */
+
+    /// Converts the virtual `index` into its [`Distance`] from the newest byte, or `None` if it has
+    /// not been written yet or is no longer in the buffer.
+    fn distance_of(&self, index: usize) -> Option<Distance>;
+
+    /// Returns the valid bytes as two slices, such that the first slice is the oldest data and
+    /// the second slice is the newest.
+    fn as_slices(&self) -> (&[u8], &[u8]);
+
+    /// Calculates the length of the match between `query` and the bytes starting at `distance`.
+    /// Like a `Rep`, the match may run past the newest byte into `query` itself. Panics if
+    /// `distance` is not valid.
+    fn match_length_at(&self, distance: Distance, query: &[u8]) -> usize;
+
+    /// Empties the buffer, keeping its allocation. Virtual indices restart from 0.
+    fn clear(&mut self);
+
+    /// Makes room for at least `capacity` bytes before the oldest ones start to be overwritten.
+    /// Every valid byte stays at the same virtual index.
+    fn grow(&mut self, capacity: usize);
 }
 
-/// See [`std::slice::SliceIndex`].
+/// See [`core::slice::SliceIndex`].
+///
+/// # Safety
+///
+/// Implementations must only return pointers from [`SliceIndex::get_unchecked`] that are in
+/// bounds of `slice` whenever [`SliceIndex::get`] would return `Some`.
 pub unsafe trait SliceIndex<T: ?Sized> {
     type Output: ?Sized;
 
     fn get(self, slice: &T) -> Option<&Self::Output>;
 
+    /// # Safety
+    ///
+    /// `slice` must be a valid pointer, and the index must be in bounds.
     unsafe fn get_unchecked(self, slice: *const T) -> *const Self::Output;
 
     #[track_caller]
     fn index(self, slice: &T) -> &Self::Output;
 }
+
+// Every buffer is written through `io::Write` here, so the tests need `std`.
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::{prelude::*, *};
+    use std::io::Write;
+
+    /// Collects the valid bytes of any buffer, checking that every way of getting at them agrees.
+    fn contents<B: Buffer>(buf: &B, n: usize) -> Vec<u8> {
+        let (tail, head) = buf.as_slices();
+        let bytes = [tail, head].concat();
+        assert_eq!(buf.len(), bytes.len());
+
+        let start = n - buf.len();
+        for (i, &byte) in bytes.iter().enumerate() {
+            assert_eq!(Some(&byte), buf.get(start + i));
+            assert_eq!(byte, buf[start + i]);
+            assert_eq!(byte, unsafe { *buf.get_unchecked(start + i) });
+        }
+        assert_eq!(None, buf.get(n));
+        bytes
+    }
+
+    /// Checks matches against a buffer holding `0123abcdef`, whatever wrapped out of it before.
+    fn check_match_length_at<B: Buffer>(buf: &B) {
+        assert_eq!(2, buf.match_length_at(Distance::new(4), b"bcX"));
+        assert_eq!(0, buf.match_length_at(Distance::new(9), b"X"));
+        assert_eq!(4, buf.match_length_at(Distance::new(4), b"bcdeX"));
+        assert_eq!(10, buf.match_length_at(Distance::new(9), b"0123abcdefX"));
+
+        // past the head
+        assert_eq!(7, buf.match_length_at(Distance::new(1), b"efefefeX"));
+        assert_eq!(12, buf.match_length_at(Distance::new(7), b"23abcdef23abX"));
+        assert_eq!(5, buf.match_length_at(Distance::new(0), b"fffff"));
+        assert_eq!(0, buf.match_length_at(Distance::new(0), b""));
+    }
+
+    #[test]
+    fn test_match_length_at() {
+        let mut rb = RingBuf::with_exact_capacity(10);
+        rb.write_all(b"xyz0123abcdef").unwrap();
+        check_match_length_at(&rb);
+
+        let mut vb = VecBuf::with_capacity(4);
+        vb.write_all(b"0123abcdef").unwrap();
+        check_match_length_at(&vb);
+    }
+
+    #[test]
+    fn test_buffer_ringbuf() {
+        let mut rb = RingBuf::with_capacity(4);
+        assert!(rb.is_empty());
+        assert_eq!(b"", &contents(&rb, 0)[..]);
+
+        rb.write_all(b"abcfoo").unwrap();
+        assert_eq!(b"cfoo", &contents(&rb, 6)[..]);
+        assert_eq!(None, rb.get(1));
+    }
+
+    #[test]
+    fn test_buffer_vecbuf() {
+        let mut vb = VecBuf::with_capacity(4);
+        assert!(vb.is_empty());
+        assert_eq!(b"", &contents(&vb, 0)[..]);
+
+        vb.write_all(b"abcfoo").unwrap();
+        assert_eq!(b"abcfoo", &contents(&vb, 6)[..]);
+    }
+}
@@ -1,25 +1,108 @@
 use super::*;
+use core::fmt;
 
-/*
-impl IndexBuffer for RingBuf {
-    #[inline]
-    fn get(&self, index: usize) -> Option<&u8> {
-        // TODO: We're assuming here the buffer length never shrinks!
-        if index >= self.n - self.len && index < self.len {
-            Some(unsafe { &*self.get_unchecked(index) })
+/// Why a virtual index can't be read from a [`RingBuf`]. See [`RingBuf::try_get`].
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum IndexError {
+    /// The byte hasn't been written yet, since only `written` bytes have been.
+    NotWritten { index: usize, written: usize },
+    /// The byte has been overwritten, and `oldest` is the index of the oldest byte left.
+    Overwritten { index: usize, oldest: usize },
+}
+
+impl fmt::Display for IndexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndexError::NotWritten { index, written } => write!(
+                f,
+                "Index {} has not been written yet, only {} bytes have been.",
+                index, written
+            ),
+            IndexError::Overwritten { index, oldest } => write!(
+                f,
+                "Index {} has been overwritten, the oldest byte left is at {}.",
+                index, oldest
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for IndexError {}
+
+impl RingBuf {
+    /// Gets the byte at the virtual `index` like [`RingBuf::get`](Buffer::get), but tells apart
+    /// the two ways it can be missing: a byte that isn't written yet may still come, while an
+    /// overwritten one is gone for good.
+    pub fn try_get(&self, index: usize) -> Result<&u8, IndexError> {
+        if index >= self.n {
+            Err(IndexError::NotWritten {
+                index,
+                written: self.n,
+            })
+        } else if index < self.n - self.len {
+            Err(IndexError::Overwritten {
+                index,
+                oldest: self.n - self.len,
+            })
         } else {
-            None
+            Ok(unsafe { &*self.get_unchecked(index) })
         }
     }
 
+    /// Calculates the length of the match between `query` and the bytes starting at `distance`,
+    /// stepping over the wrap as needed. Like a `Rep`, the match may run past the head into
+    /// `query` itself. Panics if `distance` is not valid.
+    pub fn match_at(&self, distance: Distance, query: &[u8]) -> usize {
+        let start = self.index_of(distance);
+        let avail = self.n - start;
+
+        // Compare each contiguous half of the window separately.
+        let from = self.wrap(start);
+        let first = cmp::min(avail, self.buf.len() - from);
+        let mut len = match_length(&self.buf[from..from + first], query);
+        if len == first && first < avail {
+            len += match_length(&self.buf[..avail - first], &query[len..]);
+        }
+
+        // The rest of the match overlaps the query.
+        if len == avail {
+            len += match_length(&query[avail..], query);
+        }
+
+        len
+    }
+
+    /// Converts `distance` into a virtual index. Panics if it is not valid.
     #[inline(always)]
-    unsafe fn get_unchecked(&self, index: usize) -> *const u8 {
-        self.buf.get_unchecked(self.wrap(index))
+    pub(super) fn index_of(&self, distance: Distance) -> usize {
+        if distance.0 < self.len {
+            self.n - distance.0 - 1
+        } else {
+            panic!("Distance {} out of bounds.", distance.0)
+        }
+    }
+
+    /// Copies the `len` bytes starting at the virtual `start` into the start of `out`. Panics if
+    /// any of the bytes have not been written yet or have since been overwritten, or if `out` is
+    /// shorter than `len`.
+    pub fn copy_range(&self, start: usize, len: usize, out: &mut [u8]) {
+        assert!(
+            start >= self.n - self.len && start + len <= self.n,
+            "Range {}..{} out of bounds.",
+            start,
+            start + len
+        );
+
+        let out = &mut out[..len];
+        let from = self.wrap(start);
+        let first = cmp::min(len, self.buf.len() - from);
+
+        out[..first].copy_from_slice(&self.buf[from..from + first]);
+        out[first..].copy_from_slice(&self.buf[..len - first]);
     }
 }
-*/
 
-/*
 impl ops::Index<usize> for RingBuf {
     type Output = u8;
 
@@ -30,10 +113,20 @@ impl ops::Index<usize> for RingBuf {
     #[inline(always)]
     fn index(&self, index: usize) -> &Self::Output {
         self.get(index)
-            .expect(&format!("Index {} out of bounds.", index))
+            .unwrap_or_else(|| panic!("Index {} out of bounds.", index))
+    }
+}
+
+impl ops::Index<Distance> for RingBuf {
+    type Output = u8;
+
+    /// Indexing a [`RingBuf`] with a [`Distance`] indexes backwards from the last byte written.
+    #[inline(always)]
+    fn index(&self, distance: Distance) -> &Self::Output {
+        let index = self.index_of(distance);
+        unsafe { &*self.get_unchecked(index) }
     }
 }
-*/
 
 // We have a bit of a problem here... so let's leave this out for now.
 /*
@@ -75,6 +168,11 @@ impl Slice<'_> {
     pub fn len(&self) -> usize {
         self.len
     }
+
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
 }
 
 unsafe impl<'a> SliceIndex<Slice<'a>> for usize {
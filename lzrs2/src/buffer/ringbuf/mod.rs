@@ -3,13 +3,17 @@
 mod index;
 
 pub use index::*;
-use std::{cmp, io, marker::PhantomData, ops};
+use alloc::{boxed::Box, vec, vec::Vec};
+use core::{cmp, marker::PhantomData, ops};
+#[cfg(feature = "std")]
+use std::io;
 
 use super::{prelude::*, SliceIndex};
 
 /// A circular buffer with a specific capacity. Once the capacity is reached, the buffer will start
 /// overwriting itself. However, the safety of our index methods ensure that you can never
 /// accidentally get data that has been overwritten.
+#[derive(Clone, Debug)]
 pub struct RingBuf {
     /// The buffer.
     buf: Box<[u8]>,
@@ -23,45 +27,172 @@ pub struct RingBuf {
     /// The total number of bytes ever written into the buffer.
     n: usize,
 
-    /// Mask so we don't have to compute `buf.len()-1` all the time.
-    mask: usize,
+    /// Mask so we don't have to compute `buf.len()-1` all the time, if the capacity is a power of
+    /// two. Otherwise, indices are wrapped with a modulo.
+    mask: Option<usize>,
 }
 
 impl Buffer for RingBuf {
     /// Creates a buffer with the capacity of *at least* this many bytes.
     ///
     /// The actual capacity may be greater. It will round up to the nearest power of two for
-    /// efficiency purposes.
+    /// efficiency purposes, see [`RingBuf::capacity`]. Use [`RingBuf::with_exact_capacity`] to
+    /// keep the capacity as given.
     fn with_capacity(capacity: usize) -> Self {
+        Self::with_exact_capacity(capacity.next_power_of_two())
+    }
+
+    #[inline(always)]
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Gets the byte at the virtual `index` (see [`ops::Index<usize>`]), or `None` if it has not
+    /// been written yet or has since been overwritten.
+    #[inline]
+    fn get(&self, index: usize) -> Option<&u8> {
+        if index >= self.n - self.len && index < self.n {
+            Some(unsafe { &*self.get_unchecked(index) })
+        } else {
+            None
+        }
+    }
+
+    /// The pointer is always in bounds of the buffer, even for an invalid `index`.
+    #[inline(always)]
+    unsafe fn get_unchecked(&self, index: usize) -> *const u8 {
+        self.buf.get_unchecked(self.wrap(index))
+    }
+
+    /// Valid for the same indices as [`RingBuf::get`](Buffer::get).
+    #[inline]
+    fn distance_of(&self, index: usize) -> Option<Distance> {
+        // `len` never exceeds `n`, and `index < n` once in range, so neither side can underflow.
+        if index >= self.n - self.len && index < self.n {
+            Some(Distance(self.n - index - 1))
+        } else {
+            None
+        }
+    }
+
+    /// Returns slices such that the first slice is the oldest written data and the second slice is
+    /// the newest data (at the head).
+    fn as_slices(&self) -> (&[u8], &[u8]) {
+        self.peek_last(self.len)
+    }
+
+    /// See [`RingBuf::match_at`].
+    #[inline]
+    fn match_length_at(&self, distance: Distance, query: &[u8]) -> usize {
+        self.match_at(distance, query)
+    }
+
+    /// Nothing written before the clear can be indexed.
+    fn clear(&mut self) {
+        self.head = 0;
+        self.len = 0;
+        self.n = 0;
+    }
+
+    /// Reallocates the buffer with the capacity rounded up to a power of two, like
+    /// [`RingBuf::with_capacity`](Buffer::with_capacity), unless it is already as large.
+    fn grow(&mut self, capacity: usize) {
         let capacity = capacity.next_power_of_two();
+        if capacity <= self.buf.len() {
+            return;
+        }
+
+        let bytes = self.to_vec();
+        let oldest = self.n - self.len;
+        *self = Self::with_exact_capacity(capacity);
+        self.n = oldest;
+        self.head = self.wrap(oldest);
+        self.extend_from_slice(&bytes);
+    }
+}
+
+impl RingBuf {
+    /// Creates a buffer with a capacity of exactly this many bytes. Wrapping is cheapest when the
+    /// capacity is a power of two, which [`Buffer::with_capacity`] rounds up to. Panics if
+    /// `capacity` is 0.
+    pub fn with_exact_capacity(capacity: usize) -> Self {
+        assert!(capacity > 0, "Capacity must not be 0.");
         Self {
-            buf: {
-                let mut v = Vec::with_capacity(capacity);
-                unsafe {
-                    v.set_len(capacity);
-                }
-                v.into_boxed_slice()
-            },
+            buf: vec![0; capacity].into_boxed_slice(),
             head: 0,
             len: 0,
             n: 0,
-            mask: capacity - 1,
+            mask: capacity.is_power_of_two().then(|| capacity - 1),
         }
     }
-}
 
-impl RingBuf {
-    /// Reads 8 bytes in little endian order at `index`. Panics if there `index..index+8` is out
-    /// of bounds.
+    /// The number of bytes the buffer holds before it starts overwriting itself.
+    #[inline(always)]
+    pub fn capacity(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Iterates over the valid bytes from oldest to newest. Iterate in reverse to scan backwards
+    /// from the head.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &u8> {
+        let (tail, head) = self.as_slices();
+        tail.iter().chain(head)
+    }
+
+    /// Copies the valid bytes from oldest to newest into a new `Vec`.
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.len);
+        self.copy_into(&mut out);
+        out
+    }
+
+    /// Appends the valid bytes from oldest to newest to `dst`, which only allocates if it doesn't
+    /// have the room for them.
+    pub fn copy_into(&self, dst: &mut Vec<u8>) {
+        let (tail, head) = self.as_slices();
+        dst.reserve(self.len);
+        dst.extend_from_slice(tail);
+        dst.extend_from_slice(head);
+    }
+
+    /// Returns the last `n` valid bytes like [`Buffer::as_slices`], where the second slice ends at
+    /// the head. Only `len` bytes are returned if `n` is more than that.
+    pub fn peek_last(&self, n: usize) -> (&[u8], &[u8]) {
+        let n = n.min(self.len);
+        let (head, tail) = self.buf.split_at(self.head);
+
+        // Only `n` bytes behind the head are wanted.
+        //
+        // If the head slice length is smaller than `n`, then only the tail must be trimmed. It
+        // will keep the last `(n - head.len())` bytes.
+        //
+        // Otherwise, the head must be trimmed. It will keep the last `n` bytes.
+        if n > head.len() {
+            (&tail[tail.len() - (n - head.len())..], head)
+        } else {
+            (&[], &head[head.len() - n..])
+        }
+    }
+
+    /// Reads 8 bytes in little endian order at `index`. See [`ReadU64::read_u64_le_unchecked`].
+    ///
+    /// # Safety
+    ///
+    /// `index..index + 8` must be in bounds of the buffer.
     #[inline(always)]
-    fn read_u64_unchecked(&self, index: usize) -> u64 {
-        u64::from_le_bytes(self.buf[index..index + 8].try_into().unwrap())
+    unsafe fn read_u64_unchecked(&self, index: usize) -> u64 {
+        self.buf.read_u64_le_unchecked(index)
     }
 
-    /// See [`write_u64()`].
+    /// Writes 8 bytes in little endian order at `index`. See
+    /// [`WriteU64::write_u64_le_unchecked`].
+    ///
+    /// # Safety
+    ///
+    /// `index..index + 8` must be in bounds of the buffer.
     #[inline(always)]
-    fn write_u64_unchecked(&mut self, src: u64, index: usize) {
-        self.buf[index..index + 8].copy_from_slice(&u64::to_le_bytes(src));
+    unsafe fn write_u64_unchecked(&mut self, src: u64, index: usize) {
+        self.buf.write_u64_le_unchecked(index, src)
     }
 
     /// Finds bytes of remaining space ahead of `head`.
@@ -72,7 +203,10 @@ impl RingBuf {
 
     #[inline(always)]
     fn wrap(&self, index: usize) -> usize {
-        index & self.mask
+        match self.mask {
+            Some(mask) => index & mask,
+            None => index % self.buf.len(),
+        }
     }
 
     /// Wraps the offset from the head onto an index
@@ -90,32 +224,21 @@ impl RingBuf {
             self.wrap(self.head.wrapping_sub(offset as usize))
         }
     }
-
-    /// Returns slices such that the first slice is the oldest written data and the second slice is
-    /// the newest data (at the head).
-    fn as_slices(&self) -> (&[u8], &[u8]) {
-        let (head, tail) = self.buf.split_at(self.head);
-
-        // Only `self.len` bytes behind the head are valid.
-        //
-        // If the head slice length is smaller than the total length, then only the tail must be
-        // trimmed. It will keep the last `(self.len - head.len())` bytes.
-        //
-        // Otherwise, the head must be trimmed. It will keep the last `self.len` bytes.
-        if self.len > head.len() {
-            (&tail[tail.len() - (self.len - head.len())..], head)
-        } else {
-            (&[], &head[head.len() - self.len..])
-        }
-    }
 }
 
-impl io::Write for RingBuf {
+impl RingBuf {
     /// Writes all of the data into the buffer, overwriting itself as it goes along.
-    fn write(&mut self, mut buf: &[u8]) -> io::Result<usize> {
-        let len = buf.len();
+    pub fn extend_from_slice(&mut self, buf: &[u8]) {
+        self.copy_to_head(buf);
+        self.len = cmp::min(self.len + buf.len(), self.buf.len());
+        self.n += buf.len();
+    }
 
-        while buf.len() > 0 {
+    /// Copies the data in at the head and moves the head past it. The caller has to account for
+    /// it in `len` and `n`.
+    #[inline]
+    fn copy_to_head(&mut self, mut buf: &[u8]) {
+        while !buf.is_empty() {
             let ahead = cmp::min(buf.len(), self.remaining_ahead());
 
             // floor(ahead/8)*8
@@ -123,7 +246,10 @@ impl io::Write for RingBuf {
 
             // copy chunks 8 bytes at a time
             for i in (0..chunk_bytes).step_by(8) {
-                self.write_u64_unchecked(read_u64(buf, i), self.head + i);
+                // SAFETY: `i + 8 <= chunk_bytes`, which fits in both `buf` and the space ahead.
+                unsafe {
+                    self.write_u64_unchecked(buf.read_u64_le_unchecked(i), self.head + i);
+                }
             }
 
             // copy the remaining bytes
@@ -134,6 +260,61 @@ impl io::Write for RingBuf {
             self.head = self.wrap_offset(ahead);
             buf = &buf[ahead..];
         }
+    }
+
+    /// Appends `length` bytes copied from `distance` behind the head, like decoding a `Rep`. The
+    /// copy may overlap the bytes it appends when `length` is more than `distance + 1`, in which
+    /// case they repeat, since each byte is read just before the one it is copied into is written.
+    /// Panics if `distance` is not valid.
+    pub fn copy_from_distance(&mut self, distance: Distance, length: usize) {
+        let mut from = self.wrap(self.index_of(distance));
+
+        // The source is always `distance + 1` bytes behind the head, so it is never overwritten
+        // before it is read.
+        for _ in 0..length {
+            self.buf[self.head] = self.buf[from];
+            from = self.wrap(from + 1);
+            self.head = self.wrap_offset(1);
+        }
+
+        self.len = cmp::min(self.len + length, self.buf.len());
+        self.n += length;
+    }
+
+    /// Moves the oldest valid bytes out of the buffer into `buf`, oldest first, returning how many
+    /// were moved. They are consumed: the tail moves up behind them and they can no longer be
+    /// indexed.
+    pub fn consume_into(&mut self, buf: &mut [u8]) -> usize {
+        let (tail, head) = self.as_slices();
+
+        let from_tail = cmp::min(buf.len(), tail.len());
+        buf[..from_tail].copy_from_slice(&tail[..from_tail]);
+
+        let from_head = cmp::min(buf.len() - from_tail, head.len());
+        buf[from_tail..from_tail + from_head].copy_from_slice(&head[..from_head]);
+
+        let read = from_tail + from_head;
+        self.len -= read;
+        read
+    }
+}
+
+#[cfg(feature = "std")]
+impl io::Write for RingBuf {
+    /// Writes all of the data into the buffer. See [`RingBuf::extend_from_slice`].
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    /// Writes all of the slices into the buffer, one after the other, as if by
+    /// [`RingBuf::extend_from_slice`] on each of them.
+    fn write_vectored(&mut self, bufs: &[io::IoSlice<'_>]) -> io::Result<usize> {
+        let mut len = 0;
+        for buf in bufs {
+            self.copy_to_head(buf);
+            len += buf.len();
+        }
 
         self.len = cmp::min(self.len + len, self.buf.len());
         self.n += len;
@@ -145,11 +326,23 @@ impl io::Write for RingBuf {
     }
 }
 
-#[cfg(test)]
+#[cfg(feature = "std")]
+impl io::Read for RingBuf {
+    /// Reads the oldest valid bytes out of the buffer. See [`RingBuf::consume_into`].
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        Ok(self.consume_into(buf))
+    }
+}
+
+// The buffers are written through `io::Write` in nearly every test, so they need `std`.
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
     use anyhow::Result;
-    use std::{io::Write, ops::Index};
+    use std::{
+        io::{Read, Write},
+        ops::Index,
+    };
 
     macro_rules! p {
 		($($t:tt)*) => {
@@ -211,7 +404,238 @@ mod tests {
         Ok(())
     }
 
-    /*
+    #[test]
+    fn test_write_vectored() -> Result<()> {
+        let fragments: [&[u8]; 5] = [b"abc", b"", b"0123456789abcdef", b"x", b"foobar"];
+        let slices: Vec<_> = fragments.iter().map(|f| io::IoSlice::new(f)).collect();
+
+        for capacity in [8, 10, 64] {
+            let mut sequential = RingBuf::with_exact_capacity(capacity);
+            for fragment in fragments {
+                sequential.write_all(fragment)?;
+            }
+
+            let mut vectored = RingBuf::with_exact_capacity(capacity);
+            assert_eq!(26, vectored.write_vectored(&slices)?);
+            assert_eq!(sequential.as_slices(), vectored.as_slices());
+            assert_eq!(sequential.n, vectored.n);
+            assert_eq!(sequential.head, vectored.head);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_capacity() {
+        assert_eq!(128, RingBuf::with_capacity(100).capacity());
+        assert_eq!(128, RingBuf::with_capacity(128).capacity());
+        assert_eq!(1, RingBuf::with_capacity(0).capacity());
+        assert_eq!(100, RingBuf::with_exact_capacity(100).capacity());
+        assert_eq!(128, RingBuf::with_exact_capacity(128).capacity());
+    }
+
+    #[test]
+    fn test_exact_capacity() -> Result<()> {
+        let mut rb = RingBuf::with_exact_capacity(6);
+        rb.write_all(b"abcd")?;
+        test!((b"", b"abcd"), rb);
+
+        // The head wraps at 6, not at 8.
+        rb.write_all(b"efgh")?;
+        test!((b"cdef", b"gh"), rb);
+        assert_eq!(6, rb.len());
+        assert_eq!(None, rb.get(1));
+        assert_eq!(Some(&b'c'), rb.get(2));
+        assert_eq!(b'h', rb[7]);
+        assert_eq!(b"cdefgh", &rb.iter().copied().collect::<Vec<_>>()[..]);
+
+        // Writes longer than the buffer keep only the end.
+        rb.write_all(b"0123456789abcdefg")?;
+        test!((b"bcdef", b"g"), rb);
+        assert_eq!(b'b', rb[Distance::new(5)]);
+        assert_eq!(6, rb.match_at(Distance::new(5), b"bcdefg!"));
+
+        let mut out = [0; 4];
+        rb.copy_range(19, 4, &mut out);
+        assert_eq!(b"bcde", &out);
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic(expected = "Capacity must not be 0.")]
+    fn test_exact_capacity_zero() {
+        RingBuf::with_exact_capacity(0);
+    }
+
+    #[test]
+    fn test_distance_of() -> Result<()> {
+        rb! { rb[4] };
+        assert_eq!(None, rb.distance_of(0));
+
+        rb.write_all(b"abcdef")?;
+        assert_eq!(Some(Distance::new(0)), rb.distance_of(5));
+        assert_eq!(Some(Distance::new(3)), rb.distance_of(2));
+        assert_eq!(None, rb.distance_of(6));
+
+        // Right at the overwrite boundary.
+        assert_eq!(None, rb.distance_of(1));
+        rb.write_all(b"g")?;
+        assert_eq!(None, rb.distance_of(2));
+        assert_eq!(Some(Distance::new(3)), rb.distance_of(3));
+        assert_eq!(rb[3], rb[rb.distance_of(3).unwrap()]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_read() -> Result<()> {
+        rb! { rb[8] };
+        let mut buf = [0; 4];
+
+        rb.write_all(b"abcdef")?;
+        assert_eq!(4, rb.read(&mut buf)?);
+        assert_eq!(b"abcd", &buf);
+        test!((b"", b"ef"), rb);
+
+        // the read crosses the wrap boundary
+        rb.write_all(b"ghij")?;
+        test!((b"efgh", b"ij"), rb);
+        assert_eq!(4, rb.read(&mut buf)?);
+        assert_eq!(b"efgh", &buf);
+        test!((b"", b"ij"), rb);
+
+        // only the buffered bytes are read
+        assert_eq!(2, rb.read(&mut buf)?);
+        assert_eq!(b"ij", &buf[..2]);
+        assert_eq!(0, rb.read(&mut buf)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_fifo() -> Result<()> {
+        rb! { rb[8] };
+
+        rb.write_all(b"0123456789")?;
+        let mut out = vec![];
+        rb.read_to_end(&mut out)?;
+        assert_eq!(b"23456789", &out[..]);
+
+        // consumed bytes can no longer be indexed
+        assert_eq!(None, rb.get(9));
+        rb.write_all(b"ab")?;
+        assert_eq!(Some(&b'a'), rb.get(10));
+        assert_eq!(None, rb.get(9));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_iter() -> Result<()> {
+        rb! { rb[8] };
+        assert_eq!(None, rb.iter().next());
+
+        rb.write_all(b"abcdef")?;
+        assert_eq!(b"abcdef", &rb.iter().copied().collect::<Vec<_>>()[..]);
+
+        rb.write_all(b"ghij")?;
+        // ghijefab
+        assert_eq!(b"cdefghij", &rb.iter().copied().collect::<Vec<_>>()[..]);
+        assert_eq!(b"jihgfedc", &rb.iter().rev().copied().collect::<Vec<_>>()[..]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_vec() -> Result<()> {
+        rb! { rb[8] };
+        assert_eq!(Vec::<u8>::new(), rb.to_vec());
+
+        rb.write_all(b"abcdefghij")?;
+        // ghijcdef
+        assert_eq!(b"cdefghij"[..], rb.to_vec()[..]);
+
+        let mut dst = Vec::with_capacity(16);
+        dst.extend_from_slice(b"xy");
+        let ptr = dst.as_ptr();
+        rb.copy_into(&mut dst);
+        assert_eq!(b"xycdefghij"[..], dst[..]);
+        assert_eq!(ptr, dst.as_ptr());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_peek_last() -> Result<()> {
+        rb! { rb[8] };
+        assert_eq!((&b""[..], &b""[..]), rb.peek_last(4));
+
+        rb.write_all(b"abc")?;
+        assert_eq!((&b""[..], &b"bc"[..]), rb.peek_last(2));
+        assert_eq!((&b""[..], &b"abc"[..]), rb.peek_last(10));
+
+        rb.write_all(b"defghij")?;
+        // ijcdefgh
+        assert_eq!((&b""[..], &b"j"[..]), rb.peek_last(1));
+        assert_eq!((&b""[..], &b"ij"[..]), rb.peek_last(2));
+        assert_eq!((&b"h"[..], &b"ij"[..]), rb.peek_last(3));
+        assert_eq!((&b"efgh"[..], &b"ij"[..]), rb.peek_last(6));
+        assert_eq!((&b"cdefgh"[..], &b"ij"[..]), rb.peek_last(9));
+        assert_eq!(rb.as_slices(), rb.peek_last(usize::MAX));
+        assert_eq!((&b""[..], &b""[..]), rb.peek_last(0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_grow() -> Result<()> {
+        rb! { rb[4] };
+        rb.write_all(b"abcdef")?;
+        // efcd
+        rb.grow(3);
+        assert_eq!(4, rb.capacity());
+
+        rb.grow(6);
+        assert_eq!(8, rb.capacity());
+        assert_eq!(b"cdef"[..], rb.to_vec()[..]);
+        assert_eq!(None, rb.get(1));
+        assert_eq!(Some(&b'c'), rb.get(2));
+        assert_eq!(Some(&b'f'), rb.get(5));
+        assert_eq!(Some(Distance(3)), rb.distance_of(2));
+
+        // The old bytes are kept until the new capacity is used up.
+        rb.write_all(b"ghij")?;
+        assert_eq!(b"cdefghij"[..], rb.to_vec()[..]);
+        assert_eq!(Some(Distance(7)), rb.distance_of(2));
+        rb.write_all(b"k")?;
+        assert_eq!(None, rb.get(2));
+        assert_eq!(b"defghijk"[..], rb.to_vec()[..]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_clear() -> Result<()> {
+        rb! { rb[4] };
+
+        rb.write_all(b"abcfoo")?;
+        assert_eq!(4, rb.len());
+        rb.clear();
+        assert!(rb.is_empty());
+        test!((b"", b""), rb);
+        for i in 0..6 {
+            assert_eq!(None, rb.get(i));
+        }
+
+        rb.write_all(b"xy")?;
+        assert_eq!(2, rb.len());
+        test!((b"", b"xy"), rb);
+        assert_eq!(Some(&b'x'), rb.get(0));
+        assert_eq!(Some(&b'y'), rb.get(1));
+        assert_eq!(None, rb.get(2));
+
+        Ok(())
+    }
+
     #[test]
     fn test_index() -> Result<()> {
         rb! { rb[4] };
@@ -237,12 +661,158 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_try_get() -> Result<()> {
+        rb! { rb[4] };
+        assert_eq!(Err(IndexError::NotWritten { index: 0, written: 0 }), rb.try_get(0));
+
+        rb.write_all(b"abcfoo")?;
+        // oocf
+        assert_eq!(Ok(&b'c'), rb.try_get(2));
+        assert_eq!(Ok(&b'o'), rb.try_get(5));
+        assert_eq!(Err(IndexError::Overwritten { index: 1, oldest: 2 }), rb.try_get(1));
+        assert_eq!(Err(IndexError::NotWritten { index: 6, written: 6 }), rb.try_get(6));
+        assert_eq!(
+            "Index 1 has been overwritten, the oldest byte left is at 2.",
+            rb.try_get(1).unwrap_err().to_string()
+        );
+
+        rb.clear();
+        assert_eq!(Err(IndexError::NotWritten { index: 2, written: 0 }), rb.try_get(2));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_copy_range() -> Result<()> {
+        rb! { rb[8] };
+        let mut out = [0; 8];
+
+        rb.write_all(b"abcdef")?;
+        rb.copy_range(1, 3, &mut out);
+        assert_eq!(b"bcd", &out[..3]);
+
+        rb.write_all(b"ghij")?;
+        // ghijefab, 2..10 are valid
+        rb.copy_range(5, 4, &mut out);
+        assert_eq!(b"fghi", &out[..4]);
+        rb.copy_range(2, 8, &mut out);
+        assert_eq!(b"cdefghij", &out);
+        rb.copy_range(10, 0, &mut out);
+
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_copy_range_panic_overwritten() {
+        rb! { rb[4] };
+        rb.write_all(b"abcfoo").unwrap();
+        rb.copy_range(1, 2, &mut [0; 2]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_copy_range_panic_out_of_bounds() {
+        rb! { rb[4] };
+        rb.write_all(b"abc").unwrap();
+        rb.copy_range(2, 2, &mut [0; 2]);
+    }
+
+    #[test]
+    fn test_copy_from_distance() -> Result<()> {
+        rb! { rb[8] };
+
+        rb.write_all(b"abcdef")?;
+        rb.copy_from_distance(Distance(5), 3);
+        // cbcdefab, wrapping around the seam
+        test!((b"bcdefab", b"c"), rb);
+        assert_eq!(9, rb.n);
+
+        rb.copy_from_distance(Distance(2), 2);
+        test!((b"defab", b"cab"), rb);
+        Ok(())
+    }
+
+    #[test]
+    fn test_copy_from_distance_overlap() -> Result<()> {
+        rb! { rb[8] };
+
+        // A run of the last byte.
+        rb.write_all(b"xa")?;
+        rb.copy_from_distance(Distance(0), 4);
+        test!((b"", b"xaaaaa"), rb);
+
+        // The copy repeats the 2 bytes behind the head, even once it wraps over them.
+        rb.write_all(b"b")?;
+        rb.copy_from_distance(Distance(1), 9);
+        test!((b"babababa", b""), rb);
+        assert_eq!(b'a', rb[Distance(0)]);
+        assert_eq!(16, rb.n);
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_copy_from_distance_panic_out_of_bounds() {
+        rb! { rb[4] };
+        rb.write_all(b"abc").unwrap();
+        rb.copy_from_distance(Distance(3), 1);
+    }
+
+    #[test]
+    fn test_index_distance() -> Result<()> {
+        rb! { rb[4] };
+
+        rb.write_all(b"abcfoo")?;
+        // oocf
+        assert_eq!(b'o', rb[Distance::new(0)]);
+        assert_eq!(b'o', rb[Distance(1)]);
+        assert_eq!(b'f', rb[Distance(2)]);
+        assert_eq!(b'c', rb[Distance::new(3)]);
+
+        rb.write_all(b"x")?;
+        assert_eq!(b'x', rb[Distance::new(0)]);
+
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_index_distance_panic_overwritten() {
+        rb! { rb[4] };
+        rb.write_all(b"abcfoo").unwrap();
+        let _ = rb[Distance(4)];
+    }
+
+    #[test]
+    fn test_match_at() -> Result<()> {
+        rb! { rb[8] };
+
+        rb.write_all(b"0123abcdef")?;
+        // ef23abcd, the physical seam is between "d" and "e"
+        assert_eq!(2, rb.match_at(Distance(4), b"bcX"));
+        assert_eq!(0, rb.match_at(Distance(7), b"X"));
+
+        // across the seam
+        assert_eq!(4, rb.match_at(Distance(4), b"bcdeX"));
+        assert_eq!(7, rb.match_at(Distance(7), b"23abcdeX"));
+        assert_eq!(8, rb.match_at(Distance(7), b"23abcdef"));
+
+        // past the head
+        assert_eq!(7, rb.match_at(Distance(1), b"efefefeX"));
+        assert_eq!(12, rb.match_at(Distance(7), b"23abcdef23abX"));
+        assert_eq!(0, rb.match_at(Distance(0), b""));
+
+        Ok(())
+    }
+
     #[test]
     #[should_panic]
     fn test_index_panic_out_of_bounds() {
         rb! { rb[4] };
         rb.write_all(b"abc").unwrap();
-        rb[3];
+        let _ = rb[3];
     }
 
     #[test]
@@ -250,7 +820,6 @@ mod tests {
     fn test_index_panic_overwritten() {
         rb! { rb[4] };
         rb.write_all(b"abcfoo").unwrap();
-        rb[1];
+        let _ = rb[1];
     }
-    */
 }
@@ -0,0 +1,199 @@
+//! Extension traits for byte buffers.
+
+use super::raw;
+
+/// Reads of [`u64`]s from byte buffers, in an explicit byte order.
+pub trait ReadU64 {
+    /// Reads 8 bytes at `index` in little endian order. Panics on out of bounds.
+    fn read_u64_le(&self, index: usize) -> u64;
+
+    /// Reads 8 bytes at `index` in big endian order. Panics on out of bounds.
+    fn read_u64_be(&self, index: usize) -> u64;
+
+    /// Reads 8 bytes at `index` in little endian order without bounds checks.
+    ///
+    /// # Safety
+    ///
+    /// `index..index + 8` must be in bounds. See [`raw::read_u64_unchecked`].
+    unsafe fn read_u64_le_unchecked(&self, index: usize) -> u64;
+
+    /// Reads 8 bytes at `index` in big endian order without bounds checks.
+    ///
+    /// # Safety
+    ///
+    /// `index..index + 8` must be in bounds. See [`raw::read_u64_be_unchecked`].
+    unsafe fn read_u64_be_unchecked(&self, index: usize) -> u64;
+}
+
+/// Writes of [`u64`]s into byte buffers, in an explicit byte order.
+pub trait WriteU64 {
+    /// Writes 8 bytes at `index` in little endian order. Panics on out of bounds.
+    fn write_u64_le(&mut self, index: usize, src: u64);
+
+    /// Writes 8 bytes at `index` in big endian order. Panics on out of bounds.
+    fn write_u64_be(&mut self, index: usize, src: u64);
+
+    /// Writes 8 bytes at `index` in little endian order without bounds checks.
+    ///
+    /// # Safety
+    ///
+    /// `index..index + 8` must be in bounds. See [`raw::write_u64_unchecked`].
+    unsafe fn write_u64_le_unchecked(&mut self, index: usize, src: u64);
+
+    /// Writes 8 bytes at `index` in big endian order without bounds checks.
+    ///
+    /// # Safety
+    ///
+    /// `index..index + 8` must be in bounds. See [`raw::write_u64_be_unchecked`].
+    unsafe fn write_u64_be_unchecked(&mut self, index: usize, src: u64);
+}
+
+impl ReadU64 for [u8] {
+    #[track_caller]
+    #[inline(always)]
+    fn read_u64_le(&self, index: usize) -> u64 {
+        raw::read_u64(self, index)
+    }
+
+    #[track_caller]
+    #[inline(always)]
+    fn read_u64_be(&self, index: usize) -> u64 {
+        raw::read_u64_be(self, index)
+    }
+
+    #[inline(always)]
+    unsafe fn read_u64_le_unchecked(&self, index: usize) -> u64 {
+        raw::read_u64_unchecked(self, index)
+    }
+
+    #[inline(always)]
+    unsafe fn read_u64_be_unchecked(&self, index: usize) -> u64 {
+        raw::read_u64_be_unchecked(self, index)
+    }
+}
+
+impl WriteU64 for [u8] {
+    #[track_caller]
+    #[inline(always)]
+    fn write_u64_le(&mut self, index: usize, src: u64) {
+        raw::write_u64(self, index, src)
+    }
+
+    #[track_caller]
+    #[inline(always)]
+    fn write_u64_be(&mut self, index: usize, src: u64) {
+        raw::write_u64_be(self, index, src)
+    }
+
+    #[inline(always)]
+    unsafe fn write_u64_le_unchecked(&mut self, index: usize, src: u64) {
+        raw::write_u64_unchecked(self, index, src)
+    }
+
+    #[inline(always)]
+    unsafe fn write_u64_be_unchecked(&mut self, index: usize, src: u64) {
+        raw::write_u64_be_unchecked(self, index, src)
+    }
+}
+
+/// Fast comparisons between byte buffers.
+pub trait FastCmp<T> {
+    /// Calculates the length of the prefix match between the two buffers. See
+    /// [`raw::match_length`].
+    fn match_length(&self, other: T) -> usize;
+
+    /// Calculates the length of the prefix match between the two buffers, up to at most `max`
+    /// bytes. See [`raw::match_length_max`].
+    fn match_length_max(&self, other: T, max: usize) -> usize;
+
+    /// Calculates the length of the suffix match between the two buffers, comparing backwards
+    /// from the end of each. See [`raw::match_length_back`].
+    fn match_length_back(&self, other: T) -> usize;
+
+    /// Finds the index of the first byte that differs between the two buffers, or returns `None`
+    /// if one is a prefix of the other. See [`raw::first_mismatch`].
+    fn first_mismatch(&self, other: T) -> Option<usize>;
+}
+
+impl<T: AsRef<[u8]>> FastCmp<T> for [u8] {
+    #[inline(always)]
+    fn match_length(&self, other: T) -> usize {
+        raw::match_length(self, other.as_ref())
+    }
+
+    #[inline(always)]
+    fn match_length_max(&self, other: T, max: usize) -> usize {
+        raw::match_length_max(self, other.as_ref(), max)
+    }
+
+    #[inline(always)]
+    fn match_length_back(&self, other: T) -> usize {
+        raw::match_length_back(self, other.as_ref())
+    }
+
+    #[inline(always)]
+    fn first_mismatch(&self, other: T) -> Option<usize> {
+        raw::first_mismatch(self, other.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rw_u64() {
+        let mut buf = [0; 16];
+        let v = 0x0102_0304_0506_0708;
+
+        buf.write_u64_le(0, v);
+        buf.write_u64_be(8, v);
+        assert_eq!(buf[..8], v.to_le_bytes());
+        assert_eq!(buf[8..], v.to_be_bytes());
+        assert_eq!(v, buf.read_u64_le(0));
+        assert_eq!(v, buf.read_u64_be(8));
+        assert_eq!(v.swap_bytes(), buf.read_u64_be(0));
+
+        unsafe {
+            buf.write_u64_be_unchecked(3, v);
+            assert_eq!(v, buf.read_u64_be_unchecked(3));
+            buf.write_u64_le_unchecked(3, v);
+            assert_eq!(v, buf.read_u64_le_unchecked(3));
+        }
+    }
+
+    #[test]
+    fn test_fastcmp_match_length() {
+        let lhs: &[u8] = b"abcdefg_0123456_";
+        assert_eq!(11, lhs.match_length(b"abcdefg_012"));
+        assert_eq!(11, lhs.match_length(&b"abcdefg_012"[..]));
+        assert_eq!(11, lhs.match_length(b"abcdefg_012".to_vec()));
+        assert_eq!(0, lhs.match_length(b""));
+    }
+
+    #[test]
+    fn test_fastcmp_match_length_max() {
+        let lhs: &[u8] = b"abcdefg_0123456_";
+        assert_eq!(4, lhs.match_length_max(b"abcdefg_012", 4));
+        assert_eq!(11, lhs.match_length_max(b"abcdefg_012", 100));
+    }
+
+    #[test]
+    fn test_fastcmp_first_mismatch() {
+        let lhs: &[u8] = b"abcdefg_0123456_";
+        assert_eq!(Some(3), lhs.first_mismatch(b"abcXefg_0123456_"));
+        assert_eq!(Some(13), lhs.first_mismatch(&b"abcdefg_01234X6_"[..]));
+        assert_eq!(Some(8), lhs.first_mismatch(b"abcdefg_X".to_vec()));
+        assert_eq!(None, lhs.first_mismatch(b"abcdefg_012"));
+        assert_eq!(None, lhs.first_mismatch(b""));
+    }
+
+    #[test]
+    fn test_fastcmp_match_length_back() {
+        let lhs: &[u8] = b"_6543210_gfedcba";
+        assert_eq!(11, lhs.match_length_back(b"210_gfedcba"));
+        assert_eq!(11, lhs.match_length_back(&b"210_gfedcba"[..]));
+        assert_eq!(11, lhs.match_length_back(b"210_gfedcba".to_vec()));
+        assert_eq!(0, lhs.match_length_back(b""));
+    }
+}
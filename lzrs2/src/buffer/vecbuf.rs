@@ -0,0 +1,247 @@
+//! Provides a growable, non-wrapping buffer.
+
+use alloc::vec::Vec;
+use core::ops;
+#[cfg(feature = "std")]
+use std::io;
+
+use super::{prelude::*, Distance};
+
+/// A buffer that keeps everything ever written to it. Since it never wraps, the whole history is
+/// one contiguous slice, which keeps matching simple when all of the input fits in memory.
+#[derive(Clone, Default, Debug)]
+pub struct VecBuf {
+    buf: Vec<u8>,
+}
+
+impl Buffer for VecBuf {
+    /// Creates an empty buffer with space for `capacity` bytes before it reallocates.
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            buf: Vec::with_capacity(capacity),
+        }
+    }
+
+    #[inline(always)]
+    fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    #[inline(always)]
+    fn get(&self, index: usize) -> Option<&u8> {
+        self.buf.get(index)
+    }
+
+    #[inline(always)]
+    unsafe fn get_unchecked(&self, index: usize) -> *const u8 {
+        self.buf.as_ptr().add(index)
+    }
+
+    #[inline]
+    fn distance_of(&self, index: usize) -> Option<Distance> {
+        if index < self.buf.len() {
+            Some(Distance(self.buf.len() - index - 1))
+        } else {
+            None
+        }
+    }
+
+    /// The whole buffer is the second slice.
+    #[inline(always)]
+    fn as_slices(&self) -> (&[u8], &[u8]) {
+        (&[], &self.buf)
+    }
+
+    /// See [`VecBuf::match_at`].
+    #[inline]
+    fn match_length_at(&self, distance: Distance, query: &[u8]) -> usize {
+        self.match_at(distance, query)
+    }
+
+    fn clear(&mut self) {
+        self.buf.clear();
+    }
+
+    /// Nothing is ever overwritten, so this only reserves the space up front.
+    fn grow(&mut self, capacity: usize) {
+        self.buf.reserve(capacity.saturating_sub(self.buf.len()));
+    }
+}
+
+impl VecBuf {
+    /// Appends all of `buf` to the buffer.
+    #[inline]
+    pub fn extend_from_slice(&mut self, buf: &[u8]) {
+        self.buf.extend_from_slice(buf);
+    }
+
+    /// Iterates over the bytes from oldest to newest.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &u8> {
+        self.buf.iter()
+    }
+
+    /// Calculates the length of the match between `query` and the bytes starting at `distance`.
+    /// Like a `Rep`, the match may run past the end into `query` itself. Panics if `distance` is
+    /// not valid.
+    pub fn match_at(&self, distance: Distance, query: &[u8]) -> usize {
+        let start = self.index_of(distance);
+        let avail = self.buf.len() - start;
+
+        let mut len = match_length(&self.buf[start..], query);
+        if len == avail {
+            len += match_length(&query[avail..], query);
+        }
+
+        len
+    }
+
+    /// Copies the `len` bytes starting at `start` into the start of `out`. Panics if any of the
+    /// bytes have not been written yet, or if `out` is shorter than `len`.
+    pub fn copy_range(&self, start: usize, len: usize, out: &mut [u8]) {
+        out[..len].copy_from_slice(&self.buf[start..start + len]);
+    }
+
+    /// Converts `distance` into an index. Panics if it is not valid.
+    #[inline(always)]
+    fn index_of(&self, distance: Distance) -> usize {
+        if distance.0 < self.buf.len() {
+            self.buf.len() - distance.0 - 1
+        } else {
+            panic!("Distance {} out of bounds.", distance.0)
+        }
+    }
+}
+
+impl ops::Index<usize> for VecBuf {
+    type Output = u8;
+
+    #[inline(always)]
+    fn index(&self, index: usize) -> &Self::Output {
+        self.get(index)
+            .unwrap_or_else(|| panic!("Index {} out of bounds.", index))
+    }
+}
+
+impl ops::Index<Distance> for VecBuf {
+    type Output = u8;
+
+    /// Indexing a [`VecBuf`] with a [`Distance`] indexes backwards from the last byte written.
+    #[inline(always)]
+    fn index(&self, distance: Distance) -> &Self::Output {
+        &self.buf[self.index_of(distance)]
+    }
+}
+
+#[cfg(feature = "std")]
+impl io::Write for VecBuf {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl From<Vec<u8>> for VecBuf {
+    fn from(buf: Vec<u8>) -> Self {
+        Self { buf }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "std")]
+    use anyhow::Result;
+    #[cfg(feature = "std")]
+    use std::io::Write;
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_write() -> Result<()> {
+        let mut vb = VecBuf::with_capacity(4);
+        assert_eq!((&[][..], &[][..]), vb.as_slices());
+
+        vb.write_all(b"abcfoo")?;
+        assert_eq!((&[][..], &b"abcfoo"[..]), vb.as_slices());
+        assert_eq!(6, vb.len());
+        assert_eq!(b"abcfoo", &vb.iter().copied().collect::<Vec<_>>()[..]);
+
+        vb.clear();
+        assert!(vb.is_empty());
+        assert_eq!(None, vb.get(0));
+
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_index() -> Result<()> {
+        let mut vb = VecBuf::with_capacity(4);
+
+        vb.write_all(b"abc")?;
+        assert_eq!(b'a', vb[0]);
+        assert_eq!(Some(&b'c'), vb.get(2));
+        assert_eq!(None, vb.get(3));
+        assert_eq!(b'b', unsafe { *vb.get_unchecked(1) });
+
+        // nothing is ever overwritten
+        vb.write_all(b"foo")?;
+        assert_eq!(b'a', vb[0]);
+        assert_eq!(b'f', vb[3]);
+        assert_eq!(b'o', vb[Distance::new(0)]);
+        assert_eq!(b'a', vb[Distance::new(5)]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_distance_of() {
+        let vb = VecBuf::from(b"abc".to_vec());
+        assert_eq!(Some(Distance::new(0)), vb.distance_of(2));
+        assert_eq!(Some(Distance::new(2)), vb.distance_of(0));
+        assert_eq!(None, vb.distance_of(3));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_index_panic_out_of_bounds() {
+        let vb = VecBuf::from(b"abc".to_vec());
+        let _ = vb[3];
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_index_distance_panic_out_of_bounds() {
+        let vb = VecBuf::from(b"abc".to_vec());
+        let _ = vb[Distance::new(3)];
+    }
+
+    #[test]
+    fn test_copy_range() {
+        let vb = VecBuf::from(b"0123abcdef".to_vec());
+        let mut out = [0; 10];
+
+        vb.copy_range(5, 4, &mut out);
+        assert_eq!(b"bcde", &out[..4]);
+        vb.copy_range(0, 10, &mut out);
+        assert_eq!(b"0123abcdef", &out);
+    }
+
+    #[test]
+    fn test_match_at() {
+        let vb = VecBuf::from(b"0123abcdef".to_vec());
+
+        assert_eq!(2, vb.match_at(Distance::new(4), b"bcX"));
+        assert_eq!(0, vb.match_at(Distance::new(9), b"X"));
+        assert_eq!(4, vb.match_at(Distance::new(4), b"bcdeX"));
+        assert_eq!(10, vb.match_at(Distance::new(9), b"0123abcdefX"));
+
+        // past the end
+        assert_eq!(7, vb.match_at(Distance::new(1), b"efefefeX"));
+        assert_eq!(12, vb.match_at(Distance::new(7), b"23abcdef23abX"));
+    }
+}
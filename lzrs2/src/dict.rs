@@ -8,7 +8,9 @@ impl<B> Dictionary<B>
 where
     B: Buffer,
 {
-    pub fn with_capacity(capacity: usize) {
-        Self {}
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            buffer: B::with_capacity(capacity),
+        }
     }
 }